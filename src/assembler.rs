@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+
+use crate::cpu::{AddressingMode, Variant};
+use crate::opcodes::Instruction;
+
+// A tiny two pass 6502 assembler, mostly used to write readable CPU tests.
+//
+// Supported syntax:
+//   label:              defines a label at the current address
+//   LDA #$40            immediate ($hex, %binary or decimal)
+//   LDA $10 / $1234     zero page / absolute, with optional ,X or ,Y
+//   JMP ($FFFC)         indirect
+//   LDA ($10)           zero page indirect (65C02)
+//   LDA ($10,X)         indexed indirect
+//   JMP ($1234,X)       absolute indexed indirect (65C02)
+//   LDA ($10),Y         indirect indexed
+//   ASL A               accumulator
+//   BNE label           branch targets are turned into relative offsets
+//   .org $8000          moves the current address forward
+//   .byte $01, 2        raw bytes
+//   .word label, $1234  raw little endian words
+//   ; comment
+
+enum Operand {
+    Implied,
+    Immediate(Value),
+    Address(Value, Option<char>),
+    Indirect(Value),
+    IndirectX(Value),
+    IndirectY(Value),
+}
+
+enum Value {
+    Number(u16, bool), // The bool is set when the number fits in the zero page
+    Label(String),
+}
+
+struct Statement {
+    line: usize,
+    addr: u16,
+    kind: StatementKind,
+}
+
+enum StatementKind {
+    Instruction(&'static Instruction, Operand),
+    Bytes(Vec<Value>),
+    Words(Vec<Value>),
+}
+
+// Assembles source into bytes, starting at origin. Errors report the 1-based source line.
+pub fn assemble(source: &str, origin: u16) -> Result<Vec<u8>, String> {
+    assemble_for(Variant::default(), source, origin)
+}
+
+// Assembles with the instruction set of the given CPU variant
+pub fn assemble_for(variant: Variant, source: &str, origin: u16) -> Result<Vec<u8>, String> {
+    let mut labels: HashMap<String, u16> = HashMap::new();
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut addr = origin;
+
+    // First pass: work out the size of every statement to place the labels
+    for (index, raw_line) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut text = raw_line.split(';').next().unwrap_or("").trim();
+
+        if let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("line {}: invalid label '{}'", line, label));
+            }
+            if labels.insert(label.to_string(), addr).is_some() {
+                return Err(format!("line {}: label '{}' defined twice", line, label));
+            }
+            text = text[colon + 1..].trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = match text.find(char::is_whitespace) {
+            Some(space) => (&text[..space], text[space..].trim()),
+            None => (text, ""),
+        };
+        let mnemonic = mnemonic.to_ascii_uppercase();
+
+        let kind = match mnemonic.as_str() {
+            ".ORG" => {
+                let target = match parse_value(rest).map_err(|e| format!("line {}: {}", line, e))? {
+                    Value::Number(value, _) => value,
+                    Value::Label(_) => return Err(format!("line {}: .org needs a number", line)),
+                };
+                if target < addr {
+                    return Err(format!("line {}: .org ${:04X} is behind the current address ${:04X}", line, target, addr));
+                }
+                addr = target;
+                continue;
+            },
+            ".BYTE" => StatementKind::Bytes(parse_list(rest).map_err(|e| format!("line {}: {}", line, e))?),
+            ".WORD" => StatementKind::Words(parse_list(rest).map_err(|e| format!("line {}: {}", line, e))?),
+            _ => {
+                let operand = parse_operand(rest).map_err(|e| format!("line {}: {}", line, e))?;
+                let instruction = find_instruction(variant, &mnemonic, &operand)
+                    .ok_or_else(|| format!("line {}: no addressing mode of {} matches '{}'", line, mnemonic, rest))?;
+                StatementKind::Instruction(instruction, operand)
+            },
+        };
+
+        let size = match &kind {
+            StatementKind::Instruction(instruction, _) => instruction.length(),
+            StatementKind::Bytes(values) => values.len() as u16,
+            StatementKind::Words(values) => values.len() as u16 * 2,
+        };
+
+        statements.push(Statement { line, addr, kind });
+        addr = addr.wrapping_add(size);
+    }
+
+    // Second pass: emit the bytes now that every label is known
+    let mut output: Vec<u8> = Vec::new();
+
+    for statement in &statements {
+        let offset = statement.addr.wrapping_sub(origin) as usize;
+        if output.len() < offset {
+            output.resize(offset, 0x00);
+        }
+
+        let resolve = |value: &Value| -> Result<u16, String> {
+            match value {
+                Value::Number(number, _) => Ok(*number),
+                Value::Label(label) => labels.get(label).copied()
+                    .ok_or_else(|| format!("line {}: unknown label '{}'", statement.line, label)),
+            }
+        };
+
+        match &statement.kind {
+            StatementKind::Bytes(values) => {
+                for value in values {
+                    output.push(resolve(value)? as u8);
+                }
+            },
+            StatementKind::Words(values) => {
+                for value in values {
+                    let word = resolve(value)?;
+                    output.push(word as u8);
+                    output.push((word >> 8) as u8);
+                }
+            },
+            StatementKind::Instruction(instruction, operand) => {
+                output.push(instruction.hexcode);
+
+                let value = match operand {
+                    Operand::Implied => continue,
+                    Operand::Immediate(value) | Operand::Address(value, _) | Operand::Indirect(value) |
+                    Operand::IndirectX(value) | Operand::IndirectY(value) => resolve(value)?,
+                };
+
+                if instruction.addrmode == AddressingMode::REL {
+                    let offset = value as i32 - statement.addr.wrapping_add(2) as i32;
+                    if !(-128..=127).contains(&offset) {
+                        return Err(format!("line {}: branch target ${:04X} is out of range", statement.line, value));
+                    }
+                    output.push(offset as u8);
+                } else if instruction.length() == 2 {
+                    if value > 0xFF {
+                        return Err(format!("line {}: operand ${:X} does not fit in a byte", statement.line, value));
+                    }
+                    output.push(value as u8);
+                } else {
+                    output.push(value as u8);
+                    output.push((value >> 8) as u8);
+                }
+            },
+        }
+    }
+
+    Ok(output)
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let text = text.trim();
+
+    let parsed = if let Some(hex) = text.strip_prefix('$') {
+        u16::from_str_radix(hex, 16).map(|value| Value::Number(value, hex.len() <= 2))
+    } else if let Some(binary) = text.strip_prefix('%') {
+        u16::from_str_radix(binary, 2).map(|value| Value::Number(value, value <= 0xFF))
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        text.parse::<u16>().map(|value| Value::Number(value, value <= 0xFF))
+    } else if !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Ok(Value::Label(text.to_string()));
+    } else {
+        return Err(format!("invalid value '{}'", text));
+    };
+
+    parsed.map_err(|_| format!("invalid number '{}'", text))
+}
+
+fn parse_list(text: &str) -> Result<Vec<Value>, String> {
+    text.split(',').map(parse_value).collect()
+}
+
+fn parse_operand(text: &str) -> Result<Operand, String> {
+    let upper = text.to_ascii_uppercase().replace(' ', "");
+
+    if upper.is_empty() || upper == "A" {
+        return Ok(Operand::Implied);
+    }
+
+    if let Some(immediate) = text.trim().strip_prefix('#') {
+        return Ok(Operand::Immediate(parse_value(immediate)?));
+    }
+
+    if let Some(inner) = upper.strip_prefix('(') {
+        let original = &text.trim()[1..].replace(' ', "");
+        if let Some(ptr) = inner.strip_suffix(",X)") {
+            return Ok(Operand::IndirectX(parse_value(&original[..ptr.len()])?));
+        }
+        if let Some(ptr) = inner.strip_suffix("),Y") {
+            return Ok(Operand::IndirectY(parse_value(&original[..ptr.len()])?));
+        }
+        if let Some(ptr) = inner.strip_suffix(')') {
+            return Ok(Operand::Indirect(parse_value(&original[..ptr.len()])?));
+        }
+        return Err(format!("invalid indirect operand '{}'", text));
+    }
+
+    let original = text.replace(' ', "");
+    if let Some(addr) = upper.strip_suffix(",X") {
+        return Ok(Operand::Address(parse_value(&original[..addr.len()])?, Some('X')));
+    }
+    if let Some(addr) = upper.strip_suffix(",Y") {
+        return Ok(Operand::Address(parse_value(&original[..addr.len()])?, Some('Y')));
+    }
+
+    Ok(Operand::Address(parse_value(&original)?, None))
+}
+
+// Picks the opcode for a mnemonic and operand, preferring the zero page forms and the official encodings
+fn find_instruction(variant: Variant, mnemonic: &str, operand: &Operand) -> Option<&'static Instruction> {
+    let zero_page = |value: &Value| matches!(value, Value::Number(_, true));
+
+    let modes: Vec<AddressingMode> = match operand {
+        Operand::Implied => vec![AddressingMode::IMP, AddressingMode::ACC],
+        Operand::Immediate(_) => vec![AddressingMode::IMM],
+        Operand::Address(value, None) if zero_page(value) => vec![AddressingMode::REL, AddressingMode::ZP0, AddressingMode::ABS],
+        Operand::Address(_, None) => vec![AddressingMode::REL, AddressingMode::ABS],
+        Operand::Address(value, Some('X')) if zero_page(value) => vec![AddressingMode::ZPX, AddressingMode::ABX],
+        Operand::Address(_, Some('X')) => vec![AddressingMode::ABX],
+        Operand::Address(value, _) if zero_page(value) => vec![AddressingMode::ZPY, AddressingMode::ABY],
+        Operand::Address(_, _) => vec![AddressingMode::ABY],
+        Operand::Indirect(value) if zero_page(value) => vec![AddressingMode::IZP, AddressingMode::IND],
+        Operand::Indirect(_) => vec![AddressingMode::IND],
+        Operand::IndirectX(value) if zero_page(value) => vec![AddressingMode::IZX, AddressingMode::IAX],
+        Operand::IndirectX(_) => vec![AddressingMode::IAX],
+        Operand::IndirectY(_) => vec![AddressingMode::IZY],
+    };
+
+    for mode in modes {
+        let mut candidates = variant.instructions().iter()
+            .filter(|instruction| format!("{:?}", instruction.operate) == mnemonic && instruction.addrmode == mode);
+
+        let first = candidates.next();
+        let official = first.into_iter().chain(candidates).find(|instruction| instruction.is_official());
+
+        if let Some(instruction) = official.or(first) {
+            return Some(instruction);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+pub mod testing {
+    use super::assemble_for;
+    use crate::cartridge::Cartridge;
+    use crate::cpu::{Variant, CPU};
+    use crate::memory::{FlatMemory, Memory};
+
+    // Where test programs are placed and where BRK jumps to
+    pub const PROGRAM_START: u16 = 0x8000;
+    const BRK_VECTOR: u16 = 0xFFF0;
+    // Stops runaway programs that never reach BRK
+    const MAX_INSTRUCTIONS: usize = 100_000;
+
+    // 32 KiB image of $8000-$FFFF holding the program, with the reset vector pointing at it
+    fn program_rom(variant: Variant, source: &str) -> Vec<u8> {
+        let program = assemble_for(variant, source, PROGRAM_START).unwrap_or_else(|e| panic!("{}", e));
+
+        let mut prg_rom = vec![0x00; 0x8000];
+        prg_rom[..program.len()].copy_from_slice(&program);
+
+        let vectors = [(0xFFFA, BRK_VECTOR), (0xFFFC, PROGRAM_START), (0xFFFE, BRK_VECTOR)];
+        for (vector, target) in vectors {
+            prg_rom[(vector - 0x8000) as usize] = target as u8;
+            prg_rom[(vector - 0x8000) as usize + 1] = (target >> 8) as u8;
+        }
+
+        prg_rom
+    }
+
+    // Builds a 32 KiB NROM cartridge holding the program
+    pub fn cartridge(source: &str) -> Cartridge {
+        let mut image = vec![0x4E, 0x45, 0x53, 0x1A, 0x02, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        image.extend_from_slice(&program_rom(Variant::Ricoh2A03, source));
+        image.extend_from_slice(&[0x00; 0x2000]);
+
+        Cartridge::from_bytes(&image).unwrap()
+    }
+
+    // Flat 64 KiB memory holding the program
+    pub fn flat_memory(source: &str) -> FlatMemory {
+        flat_memory_for(Variant::Ricoh2A03, source)
+    }
+
+    pub fn flat_memory_for(variant: Variant, source: &str) -> FlatMemory {
+        let mut memory = FlatMemory::new();
+        memory.load(PROGRAM_START, &program_rom(variant, source));
+        memory
+    }
+
+    // Runs a powered on CPU until it is about to execute BRK, returning the CPU in the state right before the BRK
+    pub fn run_until_brk<M: Memory>(mut cpu: CPU<M>) -> CPU<M> {
+        for _ in 0..MAX_INSTRUCTIONS {
+            if cpu.peek(cpu.program_counter) == 0x00 {
+                return cpu;
+            }
+            if let Some(addr) = cpu.jammed {
+                panic!("CPU jammed at ${:04X}", addr);
+            }
+            cpu.step();
+        }
+
+        panic!("program did not reach BRK within {} instructions", MAX_INSTRUCTIONS);
+    }
+
+    // Assembles and runs a program on a flat 64 KiB memory
+    pub fn run(source: &str) -> CPU<FlatMemory> {
+        run_variant(Variant::Ricoh2A03, source)
+    }
+
+    pub fn run_variant(variant: Variant, source: &str) -> CPU<FlatMemory> {
+        let mut cpu = CPU::with_memory(flat_memory_for(variant, source));
+        cpu.variant = variant;
+        cpu.power_on();
+        run_until_brk(cpu)
+    }
+
+    // Assembles and runs a program from a cartridge on the NES bus
+    pub fn run_nes(source: &str) -> CPU {
+        run_until_brk(CPU::new(cartridge(source)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addressing_modes() {
+        let program = assemble("
+            LDA #$40
+            LDA $10
+            LDA $10,X
+            LDX $10,Y
+            LDA $1234
+            LDA $1234,X
+            LDA $1234,Y
+            LDA ($10,X)
+            LDA ($10),Y
+            JMP ($FFFC)
+            ASL A
+            ASL
+            INX
+        ", 0x8000).unwrap();
+
+        assert_eq!(program, vec![
+            0xA9, 0x40,
+            0xA5, 0x10,
+            0xB5, 0x10,
+            0xB6, 0x10,
+            0xAD, 0x34, 0x12,
+            0xBD, 0x34, 0x12,
+            0xB9, 0x34, 0x12,
+            0xA1, 0x10,
+            0xB1, 0x10,
+            0x6C, 0xFC, 0xFF,
+            0x0A,
+            0x0A,
+            0xE8,
+        ]);
+    }
+
+    #[test]
+    fn numbers_and_zero_page_selection() {
+        let program = assemble("LDA #%1010\nLDA #10\nLDA $0010\nSTA 300", 0x8000).unwrap();
+        assert_eq!(program, vec![0xA9, 0x0A, 0xA9, 0x0A, 0xAD, 0x10, 0x00, 0x8D, 0x2C, 0x01]);
+    }
+
+    #[test]
+    fn labels_and_branches() {
+        let program = assemble("
+            start:
+                LDX #$03
+            loop: DEX       ; count down
+                BNE loop
+                JMP start
+                JSR end
+            end:
+                RTS
+        ", 0xC000).unwrap();
+
+        assert_eq!(program, vec![0xA2, 0x03, 0xCA, 0xD0, 0xFD, 0x4C, 0x00, 0xC0, 0x20, 0x0B, 0xC0, 0x60]);
+    }
+
+    #[test]
+    fn directives() {
+        let program = assemble("
+            .byte $01, 2
+            .word data
+            .org $8008
+            data: .byte %11
+        ", 0x8000).unwrap();
+
+        assert_eq!(program, vec![0x01, 0x02, 0x08, 0x80, 0x00, 0x00, 0x00, 0x00, 0x03]);
+    }
+
+    #[test]
+    fn prefers_official_encodings() {
+        assert_eq!(assemble("SBC #$01", 0x8000).unwrap(), vec![0xE9, 0x01]);
+        assert_eq!(assemble("NOP", 0x8000).unwrap(), vec![0xEA]);
+        assert_eq!(assemble("LAX $10", 0x8000).unwrap(), vec![0xA7, 0x10]);
+    }
+
+    #[test]
+    fn cmos_instructions() {
+        let program = assemble_for(Variant::Cmos65C02, "
+            LDA ($10)
+            JMP ($1234,X)
+            JMP ($1234)
+            STZ $10
+            PHX
+            BRA $8000
+        ", 0x8000).unwrap();
+
+        assert_eq!(program, vec![0xB2, 0x10, 0x7C, 0x34, 0x12, 0x6C, 0x34, 0x12, 0x64, 0x10, 0xDA, 0x80, 0xF3]);
+        assert!(assemble("STZ $10", 0x8000).is_err());
+    }
+
+    #[test]
+    fn errors_report_the_line() {
+        assert_eq!(assemble("NOP\nLDX ($10),Y", 0x8000).unwrap_err(), "line 2: no addressing mode of LDX matches '($10),Y'");
+        assert_eq!(assemble("BNE nowhere", 0x8000).unwrap_err(), "line 1: unknown label 'nowhere'");
+        assert!(assemble("loop: NOP\n.org $8100\nBNE loop", 0x8000).unwrap_err().contains("out of range"));
+    }
+}
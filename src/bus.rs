@@ -1,5 +1,14 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use crate::cartridge::Cartridge;
+use crate::cdl;
+use crate::controller::Controllers;
+use crate::debugger::AccessLog;
+use crate::interrupts::InterruptController;
 use crate::ppu::PPU;
+#[cfg(test)]
+use crate::ppu::{PPUStatusFlags, IO_LATCH_DECAY_FRAMES, WIDTH};
 
 
 // Memory addresses
@@ -7,105 +16,468 @@ const RAM: u16 = 0x0000;
 const RAM_MIRRORS_END: u16 = 0x1FFF;
 const PPU_REGISTERS: u16 = 0x2000;
 const PPU_REGISTERS_MIRRORS_END: u16 = 0x3FFF;
+const OAM_DMA: u16 = 0x4014;
+const CONTROLLER_1: u16 = 0x4016;
+const CONTROLLER_2: u16 = 0x4017;
+const PRG_RAM: u16 = 0x6000;
+const PRG_RAM_END: u16 = 0x7FFF;
+
+// What internal RAM holds at power-up. It is undefined on real hardware and a few games read it before writing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RamInit {
+    #[default]
+    Zeros,
+    Ones, // Every byte $FF
+    Random(u64), // Seeded, so a run can be reproduced
+}
+
+impl FromStr for RamInit {
+    type Err = String;
+
+    // zeros, ff, random or random:<seed>
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "zeros" => Ok(RamInit::Zeros),
+            None if s == "ff" => Ok(RamInit::Ones),
+            None if s == "random" => {
+                let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+                log::info!("RAM initialised with seed {}", seed);
+                Ok(RamInit::Random(seed))
+            },
+            Some(("random", seed)) => seed.parse().map(RamInit::Random).map_err(|_| format!("Invalid seed: {}", seed)),
+            _ => Err(format!("Unknown RAM pattern: {} (expected zeros, ff, random or random:<seed>)", s)),
+        }
+    }
+}
+
+impl RamInit {
+    pub fn fill(&self, ram: &mut [u8]) {
+        match *self {
+            RamInit::Zeros => ram.fill(0x00),
+            RamInit::Ones => ram.fill(0xFF),
+            RamInit::Random(seed) => {
+                // xorshift64*, the state must never be zero
+                let mut state = seed | 1;
+                for byte in ram.iter_mut() {
+                    state ^= state >> 12;
+                    state ^= state << 25;
+                    state ^= state >> 27;
+                    *byte = (state.wrapping_mul(0x2545F4914F6CDD1D) >> 56) as u8;
+                }
+            },
+        }
+    }
+}
 
 pub struct Bus {
     pub cpu_vram: [u8; 2048],
     pub cartridge: Cartridge,
     pub ppu: PPU,
+    pub interrupts: InterruptController,
+    pub ram_init: RamInit,
+    pub access_log: AccessLog, // CPU reads and writes, recorded for breakpoints
+    pub prg_log: Vec<u8>, // Code/data logger flags of every PRG ROM byte
+    pub open_bus: u8, // Last value on the CPU data bus, what reads of unmapped addresses return
+    pub oam_dma: Option<u8>, // Page written to $4014, the CPU copies it to OAM before its next instruction
+    pub controllers: Controllers,
 }
 
 impl Bus {
     pub fn new(cartridge: Cartridge) -> Bus {
         Bus {
             cpu_vram: [0; 2048],
+            prg_log: vec![0; cartridge.prg_rom.len()],
             ppu: PPU::new(cartridge.chr_rom.clone(), cartridge.mirror.clone()),
             cartridge,
+            interrupts: InterruptController::new(),
+            ram_init: RamInit::default(),
+            access_log: AccessLog::default(),
+            open_bus: 0,
+            oam_dma: None,
+            controllers: Controllers::default(),
         }
     }
+
+    pub fn power_on(&mut self) {
+        self.ram_init.fill(&mut self.cpu_vram);
+        self.ppu.power_on();
+        self.interrupts = InterruptController::new();
+    }
+
+    // RAM keeps its contents through a reset
+    pub fn reset(&mut self) {
+        self.ppu.reset();
+    }
 }
 
 impl Bus {
     pub fn mem_read(&mut self, addr: u16) -> u8 {
-        match addr {
+        let data = match addr {
             RAM..=RAM_MIRRORS_END => {
                 let mirror_down_addr = addr & 0x07FF;                
                 self.cpu_vram[mirror_down_addr as usize]
             },
 
             // PPU
-            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 | 0x4014 => {
-                panic!("PPU write-only register read attempted at address {:#X}", addr);
-            },
-
-            0x2002 => self.ppu.read_status_register(),
+            PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => self.ppu.read_register(addr),
 
-            0x2007 => self.ppu.read_data(),
+            // Controllers only drive the low bits
+            CONTROLLER_1 | CONTROLLER_2 => (self.open_bus & 0xE0) | self.controllers.read((addr - CONTROLLER_1) as usize, &self.ppu),
 
-            0x2008..=PPU_REGISTERS_MIRRORS_END => {
-                let mirror_down_addr = addr & 0x0007;
-                self.mem_read(mirror_down_addr)
-            },
+            PRG_RAM..=PRG_RAM_END => self.cartridge.prg_ram[(addr - PRG_RAM) as usize],
 
             // ROM(Cartridge)
             0x8000..=0xFFFF => self.read_prg_rom(addr),
             
-
+            // Nothing drives the data bus, the CPU reads what was last on it
             _ => {
-                println!("Unmapped memory address: {:#X}", addr);
-                0
+                log::debug!("Unmapped read at ${:04X}", addr);
+                self.open_bus
             }
 
-        }
+        };
+
+        self.open_bus = data;
+        data
     }
 
     pub fn mem_write(&mut self, addr: u16, data: u8) {
+        self.open_bus = data;
+
         match addr {
             RAM..=RAM_MIRRORS_END => {
-                let mirror_down_addr = addr & 0b11111111_11111111;
+                let mirror_down_addr = addr & 0x07FF;
                 self.cpu_vram[mirror_down_addr as usize] = data;
             },
 
-            0x2000 => self.ppu.write_to_control_register(data),
+            PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => self.ppu.write_register(addr, data),
 
-            0x2001 => self.ppu.write_to_mask_register(data),
+            OAM_DMA => self.oam_dma = Some(data),
 
-            0x2002 => panic!("PPU read-only register write attempted at address {:#X}", addr),
+            CONTROLLER_1 => self.controllers.write(data),
 
-            0x2003 => todo!("OAMADDR"),
+            PRG_RAM..=PRG_RAM_END => self.cartridge.prg_ram[(addr - PRG_RAM) as usize] = data,
 
-            0x2004 => todo!("OAMDATA"),
+            // Mapper registers on most cartridges, there are no mappers yet
+            0x8000..=0xFFFF => log::debug!("Write of ${:02X} to ROM at ${:04X}", data, addr),
 
-            0x2005 => todo!("PPUSCROLL"),
+            _ => log::debug!("Unmapped write of ${:02X} at ${:04X}", data, addr),
+        }
+    }
 
-            0x2006 => self.ppu.write_to_address_register(data),
+    // Reads memory without any side effects, used by the debugger and trace logger
+    pub fn mem_peek(&self, addr: u16) -> u8 {
+        match addr {
+            RAM..=RAM_MIRRORS_END => self.cpu_vram[(addr & 0x07FF) as usize],
+            PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => self.ppu.peek_register(addr),
+            CONTROLLER_1 | CONTROLLER_2 => (self.open_bus & 0xE0) | self.controllers.peek((addr - CONTROLLER_1) as usize, &self.ppu),
+            PRG_RAM..=PRG_RAM_END => self.cartridge.prg_ram[(addr - PRG_RAM) as usize],
+            0x8000..=0xFFFF => self.read_prg_rom(addr),
+            _ => self.open_bus,
+        }
+    }
 
-            0x2007 => self.ppu.write_data(data),
+    pub fn read_prg_rom(&self, addr: u16) -> u8 {
+        self.cartridge.prg_rom[self.prg_rom_offset(addr)]
+    }
 
+    // Offset in PRG ROM of the byte the CPU sees at addr ($8000-$FFFF)
+    pub fn prg_rom_offset(&self, addr: u16) -> usize {
+        let mut offset = addr as usize - 0x8000;
+        if self.cartridge.prg_rom.len() == 0x4000 && offset >= 0x4000 {
+            // Mirror
+            offset -= 0x4000;
+        }
 
-            PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => {
-                let mirror_down_addr = addr & 0b00100000_00000111;
-                self.mem_write(mirror_down_addr, data);
-            },
+        offset
+    }
 
-            0x8000..=0xFFFF => {
-                panic!("Cannot write to ROM");
-            },
+    // Picks up the .cdl file next to the ROM, if there is one
+    pub fn load_cdl(&mut self) -> Result<(), String> {
+        match self.cartridge.path.as_deref().map(cdl::path) {
+            Some(path) if path.exists() => cdl::load(&path, &mut self.prg_log, &mut self.ppu.chr_log),
+            _ => Ok(()),
+        }
+    }
 
-            _ => {
-                println!("Unmapped memory address: {:#X}", addr);
-            }
+    pub fn save_cdl(&self) -> Result<(), String> {
+        match self.cartridge.path.as_deref().map(cdl::path) {
+            Some(path) => cdl::save(&path, &self.prg_log, &self.ppu.chr_log),
+            None => Err("the ROM was not loaded from a file".to_string()),
+        }
+    }
 
+    // Only PRG ROM is logged, RAM contents don't belong to the game
+    pub fn log_code_data(&mut self, addr: u16, flags: u8) {
+        if addr >= 0x8000 {
+            let offset = self.prg_rom_offset(addr);
+            cdl::mark_prg(&mut self.prg_log, offset, addr, flags);
         }
     }
 
-    pub fn read_prg_rom(&self, mut addr: u16) -> u8 {
-        addr -= 0x8000;
-        if self.cartridge.prg_rom.len() == 0x4000 && addr >= 0x4000 {
-            // Mirror
-            addr -= 0x4000;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::{cartridge, run_nes};
+
+    #[test]
+    fn ram_init_patterns() {
+        let mut ram = [0x55; 64];
+
+        RamInit::Ones.fill(&mut ram);
+        assert!(ram.iter().all(|&b| b == 0xFF));
+
+        RamInit::Zeros.fill(&mut ram);
+        assert!(ram.iter().all(|&b| b == 0x00));
+
+        let mut other = [0x00; 64];
+        RamInit::Random(42).fill(&mut ram);
+        RamInit::Random(42).fill(&mut other);
+        assert_eq!(ram, other);
+        assert!(ram.iter().any(|&b| b != ram[0]));
+
+        RamInit::Random(1234).fill(&mut other);
+        assert_ne!(ram, other);
+    }
+
+    #[test]
+    fn ram_init_from_str() {
+        assert_eq!("zeros".parse(), Ok(RamInit::Zeros));
+        assert_eq!("ff".parse(), Ok(RamInit::Ones));
+        assert_eq!("random:7".parse(), Ok(RamInit::Random(7)));
+        assert!(matches!("random".parse(), Ok(RamInit::Random(_))));
+        assert!("random:x".parse::<RamInit>().is_err());
+        assert!("ones".parse::<RamInit>().is_err());
+    }
+
+    #[test]
+    fn power_on_fills_ram_and_reset_keeps_it() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        bus.ram_init = RamInit::Ones;
+        bus.power_on();
+        assert_eq!(bus.cpu_vram[0x0123], 0xFF);
+
+        bus.cpu_vram[0x0123] = 0x12;
+        bus.ppu.write_to_control_register(0x80);
+        bus.reset();
+
+        assert_eq!(bus.cpu_vram[0x0123], 0x12);
+        assert_eq!(bus.ppu.control_register, 0x00);
+    }
+
+    #[test]
+    fn prg_ram() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        bus.mem_write(0x6000, 0x12);
+        bus.mem_write(0x7FFF, 0x34);
+
+        assert_eq!(bus.mem_read(0x6000), 0x12);
+        assert_eq!(bus.mem_peek(0x7FFF), 0x34);
+        assert_eq!(bus.cartridge.prg_ram[0x1FFF], 0x34);
+    }
+
+    #[test]
+    fn unmapped_reads_return_open_bus() {
+        let cpu = run_nes("
+                LDA $5000
+                STA $00
+                LDX #$00
+                LDA $4020,X
+                STA $01
+                STA $8000
+                LDA $2000
+                STA $02
+            ");
+
+        // The last byte on the bus was the high byte of the address
+        assert_eq!(cpu.bus.cpu_vram[0x00], 0x50);
+        assert_eq!(cpu.bus.cpu_vram[0x01], 0x40);
+        // Write-only PPU registers return the PPU's latch, not the CPU's open bus
+        assert_eq!(cpu.bus.cpu_vram[0x02], 0x00);
+    }
+
+    #[test]
+    fn ppu_io_latch() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        bus.ppu.frame = 100;
+
+        // Writes to any register drive the latch, even to the read-only status register
+        bus.mem_write(0x2002, 0x5A);
+        assert_eq!(bus.mem_read(0x2000), 0x5A);
+        assert_eq!(bus.mem_read(0x3FFD), 0x5A);
+        bus.ppu.set_status_flag(PPUStatusFlags::VerticalBlank, true);
+        assert_eq!(bus.mem_read(0x2002), 0x80 | 0x1A);
+        // The status read drove the top bits
+        assert_eq!(bus.mem_read(0x2005), 0x9A);
+
+        // Palette reads leave the top two bits to the latch
+        bus.mem_write(0x2006, 0x3F);
+        bus.mem_write(0x2006, 0x00);
+        bus.mem_write(0x2007, 0xFF);
+        bus.mem_write(0x2006, 0x3F);
+        bus.mem_write(0x2006, 0x00);
+        bus.ppu.frame += 1;
+        bus.mem_write(0x2001, 0x80);
+        assert_eq!(bus.ppu.read_data() & 0xC0, 0x80);
+
+        // Bits decay when nothing drives them for long enough
+        bus.ppu.frame += IO_LATCH_DECAY_FRAMES;
+        assert_eq!(bus.mem_read(0x2000), 0x00);
+    }
+
+    #[test]
+    fn ppu_registers() {
+        let mut bus = Bus::new(cartridge("NOP"));
+
+        // OAMADDR and OAMDATA
+        bus.mem_write(0x2003, 0xFF);
+        bus.mem_write(0x2004, 0x12);
+        bus.mem_write(0x2004, 0x34);
+        assert_eq!((bus.ppu.oam[0xFF], bus.ppu.oam[0x00]), (0x12, 0x34));
+        bus.mem_write(0x2003, 0xFF);
+        assert_eq!(bus.mem_read(0x2004), 0x12);
+
+        // PPUSCROLL shares the write toggle with PPUADDR
+        bus.mem_write(0x2005, 0x08);
+        bus.mem_write(0x2005, 0x10);
+        assert_eq!((bus.ppu.scroll_x, bus.ppu.scroll_y), (0x08, 0x10));
+
+        // Nametables are mirrored into the 2 KiB of VRAM, $3000 mirrors $2000
+        bus.mem_write(0x2006, 0x24);
+        bus.mem_write(0x2006, 0x05);
+        bus.mem_write(0x2007, 0xAB);
+        bus.mem_write(0x2006, 0x30);
+        bus.mem_write(0x2006, 0x05);
+        bus.mem_read(0x2007);
+        assert_eq!(bus.mem_read(0x2007), 0xAB);
+
+        // Writes to ROM are ignored
+        bus.mem_write(0x8000, 0xFF);
+        assert_eq!(bus.mem_peek(0x8000), 0xEA);
+    }
+
+    #[test]
+    fn chr_ram_reads_return_the_latch() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        bus.ppu = PPU::new(vec![], crate::cartridge::Mirroring::Horizontal);
+
+        bus.mem_write(0x2006, 0x00);
+        bus.mem_write(0x2006, 0x10);
+        bus.mem_read(0x2007);
+        assert_eq!(bus.mem_read(0x2007), 0x10);
+    }
+
+    #[test]
+    fn ppu_rendering() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        // Tile 1 is solid color 1
+        let mut chr_rom = vec![0; 0x2000];
+        chr_rom[0x10..0x18].fill(0xFF);
+        bus.ppu = PPU::new(chr_rom, crate::cartridge::Mirroring::Horizontal);
+
+        // Backdrop black, background color 1 white, sprite color 1 red
+        for (addr, color) in [(0x3F00, 0x0F), (0x3F01, 0x30), (0x3F11, 0x16)] {
+            bus.mem_write(0x2006, (addr >> 8) as u8);
+            bus.mem_write(0x2006, addr as u8);
+            bus.mem_write(0x2007, color);
+        }
+        // Tile 1 in the top left corner, sprite 0 over it at (4, 1)
+        bus.mem_write(0x2006, 0x20);
+        bus.mem_write(0x2006, 0x00);
+        bus.mem_write(0x2007, 0x01);
+        bus.ppu.oam[0..4].copy_from_slice(&[0x00, 0x01, 0x00, 0x04]);
+        bus.mem_write(0x2005, 0x00);
+        bus.mem_write(0x2005, 0x00);
+        bus.mem_write(0x2001, 0x1E);
+
+        while bus.ppu.scanline < 2 {
+            bus.ppu.clock();
+        }
+
+        let row = |y: usize| &bus.ppu.frame_buffer[y * WIDTH..y * WIDTH + 16];
+        assert_eq!(row(0), &[0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F]);
+        assert_eq!(row(1), &[0x30, 0x30, 0x30, 0x30, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x0F, 0x0F, 0x0F, 0x0F]);
+        assert_ne!(bus.ppu.status_register & PPUStatusFlags::SpriteZeroHit as u8, 0);
+    }
+
+    #[test]
+    fn oam_and_sprite_size_changes_mid_scanline() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        // Tile 1 of the left pattern table and tile 0 of the right one are solid color 1
+        let mut chr_rom = vec![0; 0x2000];
+        chr_rom[0x10..0x18].fill(0xFF);
+        chr_rom[0x1000..0x1008].fill(0xFF);
+        bus.ppu = PPU::new(chr_rom, crate::cartridge::Mirroring::Horizontal);
+        bus.ppu.palette[0x11] = 0x16;
+        bus.mem_write(0x2001, 0x1E);
+
+        let run_to = |bus: &mut Bus, scanline: u16, cycle: u16| {
+            while (bus.ppu.scanline, bus.ppu.cycle) != (scanline, cycle) {
+                bus.ppu.clock();
+            }
+        };
+
+        // Sprite 0 moved down by a $2004 write after it was picked for scanline 25
+        bus.ppu.oam[0..4].copy_from_slice(&[20, 0x01, 0x00, 100]);
+        run_to(&mut bus, 25, 10);
+        bus.mem_write(0x2003, 0x00);
+        bus.mem_write(0x2004, 30);
+        run_to(&mut bus, 26, 0);
+        assert_eq!(bus.ppu.frame_buffer[25 * WIDTH + 100], 0x16);
+        assert_eq!(bus.ppu.oam[0], 30);
+
+        // A flipped 8x16 sprite, switched to 8x8 sprites partway through scanline 35
+        bus.ppu.oam[0..4].copy_from_slice(&[20, 0x01, 0x80, 100]);
+        bus.mem_write(0x2000, 0x20);
+        run_to(&mut bus, 35, 10);
+        bus.mem_write(0x2000, 0x00);
+        run_to(&mut bus, 36, 0);
+        assert_eq!(bus.ppu.frame_buffer[35 * WIDTH + 100], 0x16);
+    }
+
+    #[test]
+    fn ppu_sprite_evaluation() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        // Tile 1 is solid color 1, tile 2 only has its leftmost column
+        let mut chr_rom = vec![0; 0x2000];
+        chr_rom[0x10..0x18].fill(0xFF);
+        chr_rom[0x20..0x28].fill(0x80);
+        bus.ppu = PPU::new(chr_rom, crate::cartridge::Mirroring::Horizontal);
+
+        for (addr, color) in [(0x3F00, 0x0F), (0x3F01, 0x30), (0x3F11, 0x16), (0x3F15, 0x2A)] {
+            bus.mem_write(0x2006, (addr >> 8) as u8);
+            bus.mem_write(0x2006, addr as u8);
+            bus.mem_write(0x2007, color);
+        }
+        // Tile 1 in the first two columns of the second row of tiles
+        bus.mem_write(0x2006, 0x20);
+        bus.mem_write(0x2006, 0x20);
+        bus.mem_write(0x2007, 0x01);
+        bus.mem_write(0x2007, 0x01);
+
+        // Nine sprites on scanline 10: sprite 0 flipped, sprite 1 behind the background, the 9th is dropped
+        bus.ppu.oam[0..8].copy_from_slice(&[0x09, 0x02, 0x40, 100, 0x09, 0x01, 0x21, 12]);
+        for sprite in 2..9 {
+            bus.ppu.oam[sprite * 4..sprite * 4 + 4].copy_from_slice(&[0x09, 0x01, 0x00, 100 + 10 * sprite as u8]);
         }
+        bus.mem_write(0x2005, 0x00);
+        bus.mem_write(0x2005, 0x00);
+        // Both layers on, without their leftmost 8 pixels
+        bus.mem_write(0x2001, 0x18);
+
+        while bus.ppu.scanline < 11 {
+            bus.ppu.clock();
+        }
+
+        let pixel = |x: usize| bus.ppu.frame_buffer[10 * WIDTH + x];
+        assert_eq!((pixel(0), pixel(8)), (0x0F, 0x30));
+        assert_eq!((pixel(12), pixel(16)), (0x30, 0x2A));
+        assert_eq!((pixel(100), pixel(107)), (0x0F, 0x16));
+        assert_eq!((pixel(170), pixel(180)), (0x16, 0x0F));
 
-        self.cartridge.prg_rom[addr as usize]
+        assert_ne!(bus.ppu.status_register & PPUStatusFlags::SpriteOverflow as u8, 0);
+        assert_eq!(bus.ppu.status_register & PPUStatusFlags::SpriteZeroHit as u8, 0);
     }
-    
 }
@@ -0,0 +1,206 @@
+use std::fmt;
+
+// Warnings kept for the Call Stack tab, older ones are dropped
+const MAX_WARNINGS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FrameKind {
+    Jsr,
+    Brk,
+    Nmi,
+    Irq,
+}
+
+impl FrameKind {
+    // Left with RTI rather than RTS
+    pub fn is_interrupt(&self) -> bool {
+        *self != FrameKind::Jsr
+    }
+}
+
+impl fmt::Display for FrameKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameKind::Jsr => write!(f, "JSR"),
+            FrameKind::Brk => write!(f, "BRK"),
+            FrameKind::Nmi => write!(f, "NMI"),
+            FrameKind::Irq => write!(f, "IRQ"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    pub source: u16, // The JSR or BRK, or the instruction an interrupt came in front of
+    pub target: u16, // Subroutine or handler entered
+    pub stack_pointer: u8, // After pushing the return address (and status), where the RTS or RTI starts pulling
+}
+
+impl Frame {
+    // Where the matching RTS or RTI goes back to
+    pub fn return_address(&self) -> u16 {
+        match self.kind {
+            FrameKind::Jsr => self.source.wrapping_add(3),
+            FrameKind::Brk => self.source.wrapping_add(2),
+            FrameKind::Nmi | FrameKind::Irq => self.source,
+        }
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ${:04X} from ${:04X}", self.kind, self.target, self.source)
+    }
+}
+
+// Shadow of the calls on the hardware stack. Games are free to do what they like with the stack,
+// so whatever doesn't line up with a frame is reported as a warning and the frames are resynchronised
+// with the stack pointer: frames below it were discarded and are dropped.
+#[derive(Default)]
+pub struct CallStack {
+    pub frames: Vec<Frame>, // Outermost first
+    pub warnings: Vec<String>,
+}
+
+impl CallStack {
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.warnings.clear();
+    }
+
+    // A JSR, BRK or interrupt at stack_pointer (before its pushes) entered frame
+    pub fn push(&mut self, stack_pointer: u8, frame: Frame) {
+        self.unwind(frame.source, stack_pointer);
+        self.frames.push(frame);
+    }
+
+    // An RTS or RTI at pc with stack_pointer (before its pulls) went back to return_address
+    pub fn pop(&mut self, pc: u16, stack_pointer: u8, interrupt: bool, return_address: u16) {
+        self.unwind(pc, stack_pointer);
+
+        let instruction = if interrupt { "RTI" } else { "RTS" };
+        let Some(frame) = self.frames.last().filter(|frame| frame.stack_pointer == stack_pointer) else {
+            self.warn(format!("${:04X}: {} to ${:04X} without a matching call", pc, instruction, return_address));
+            return;
+        };
+
+        if frame.kind.is_interrupt() != interrupt {
+            self.warn(format!("${:04X}: {} leaves {}", pc, instruction, frame));
+        } else if frame.return_address() != return_address {
+            self.warn(format!("${:04X}: {} to ${:04X} instead of ${:04X}", pc, instruction, return_address, frame.return_address()));
+        }
+
+        self.frames.pop();
+    }
+
+    // On TXS, calls and returns: frames below the stack pointer were pulled or overwritten by hand
+    pub fn unwind(&mut self, pc: u16, stack_pointer: u8) {
+        let kept = self.frames.iter().take_while(|frame| frame.stack_pointer >= stack_pointer).count();
+        let dropped = self.frames.len() - kept;
+
+        if dropped > 0 {
+            self.frames.truncate(kept);
+            self.warn(format!("${:04X}: stack pointer moved to ${:02X}, dropped {} frame(s)", pc, stack_pointer, dropped));
+        }
+    }
+
+    fn warn(&mut self, warning: String) {
+        log::debug!("Call stack: {}", warning);
+
+        if self.warnings.len() == MAX_WARNINGS {
+            self.warnings.remove(0);
+        }
+        self.warnings.push(warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::{cartridge, run};
+    use crate::cpu::CPU;
+
+    #[test]
+    fn nested_calls() {
+        let cpu = run("
+                JSR outer
+                BRK
+            outer:
+                JSR inner
+            back:
+                BRK
+            inner:
+                RTS
+            ");
+
+        // inner returned, outer is still running
+        assert_eq!(cpu.call_stack.frames, vec![
+            Frame { kind: FrameKind::Jsr, source: 0x8000, target: 0x8004, stack_pointer: 0xFB },
+        ]);
+        assert!(cpu.call_stack.warnings.is_empty());
+    }
+
+    #[test]
+    fn rts_jump_table_and_txs() {
+        let cpu = run("
+                JSR sub
+                BRK
+            sub:
+                LDA #$80
+                PHA
+                LDA #$0B
+                PHA
+                RTS
+                BRK
+            target:
+                LDX #$FF
+                TXS
+                NOP
+            ");
+
+        // The RTS pulls the pushed address instead of returning, the TXS then discards the JSR frame
+        assert!(cpu.call_stack.frames.is_empty());
+        assert_eq!(cpu.call_stack.warnings, vec![
+            "$800A: RTS to $800C without a matching call".to_string(),
+            "$800E: stack pointer moved to $FF, dropped 1 frame(s)".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn return_address_changed_on_the_stack() {
+        let cpu = run("
+                JSR sub
+                BRK
+                BRK
+                BRK
+            sub:
+                PLA
+                CLC
+                ADC #$01
+                PHA
+                RTS
+            ");
+
+        assert!(cpu.call_stack.frames.is_empty());
+        assert_eq!(cpu.call_stack.warnings, vec!["$800B: RTS to $8004 instead of $8003".to_string()]);
+    }
+
+    #[test]
+    fn interrupt_frames() {
+        let mut cpu = CPU::new(cartridge("NOP\nNOP"));
+        // RTI as the NMI handler
+        cpu.bus.cartridge.prg_rom[0x7FF0] = 0x40;
+        cpu.bus.interrupts.set_nmi(true);
+
+        cpu.step();
+        assert_eq!(cpu.call_stack.frames, vec![
+            Frame { kind: FrameKind::Nmi, source: 0x8001, target: 0xFFF0, stack_pointer: 0xFA },
+        ]);
+
+        cpu.step();
+        assert!(cpu.call_stack.frames.is_empty());
+        assert!(cpu.call_stack.warnings.is_empty());
+        assert_eq!(cpu.program_counter, 0x8001);
+    }
+}
@@ -1,5 +1,8 @@
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use crate::storage;
 
 #[derive(Debug, Clone)]
 pub struct INesHeader {
@@ -45,11 +48,29 @@ pub struct Cartridge {
     pub chr_rom: Vec<u8>,
     pub mirror: Mirroring,
     pub mapper: u8,
+    pub prg_ram: Vec<u8>, // $6000-$7FFF
+    pub path: Option<PathBuf>, // File the ROM was loaded from
 }
 
 impl Cartridge {
+    // Identifies the game independently of the file name and header
+    pub fn crc32(&self) -> u32 {
+        storage::crc32([self.prg_rom.as_slice(), self.chr_rom.as_slice()])
+    }
+
     pub fn new(filename: &str) -> Result<Cartridge, String> {
         let mut file = File::open(filename).unwrap();
+        let mut cartridge = Self::from_reader(&mut file)?;
+        cartridge.path = Some(PathBuf::from(filename));
+        Ok(cartridge)
+    }
+
+    // Loads an iNES image that is already in memory
+    pub fn from_bytes(data: &[u8]) -> Result<Cartridge, String> {
+        Self::from_reader(&mut Cursor::new(data))
+    }
+
+    fn from_reader<R: Read + Seek>(file: &mut R) -> Result<Cartridge, String> {
         let mut header_buffer: Vec<u8> = vec![0; 16];
 
         file.read_exact(&mut header_buffer).unwrap();
@@ -101,6 +122,9 @@ impl Cartridge {
             chr_rom,
             mirror,
             mapper,
+            // Always 8 KiB, most headers leave the size at 0 for "infer"
+            prg_ram: vec![0; 0x2000],
+            path: None,
         })
     }
 }
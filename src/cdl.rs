@@ -0,0 +1,152 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Code/Data Logger flags, one byte per ROM byte in the same layout as FCEUX .cdl files:
+// the PRG ROM flags followed by the CHR ROM flags.
+
+// PRG ROM
+pub const CODE: u8 = 0x01;
+pub const DATA: u8 = 0x02;
+pub const BANK: u8 = 0x0C; // CPU window the byte was mapped into when last accessed, 0 for $8000-$9FFF to 3 for $E000-$FFFF
+pub const INDIRECT_CODE: u8 = 0x10; // Jumped to through JMP ($xxxx)
+pub const INDIRECT_DATA: u8 = 0x20; // Read through a pointer, LDA ($xx),Y and friends
+pub const PCM_AUDIO: u8 = 0x40; // Fetched by the DMC, there is no APU yet to set it
+
+// CHR ROM
+pub const CHR_RENDERED: u8 = 0x01; // Fetched by the PPU while drawing the picture
+pub const CHR_READ: u8 = 0x02; // Read through $2007
+
+// Adds flags to the PRG ROM byte at offset, which the CPU reached at addr
+pub fn mark_prg(log: &mut [u8], offset: usize, addr: u16, flags: u8) {
+    let bank = ((addr >> 13) & 0x03) as u8;
+    log[offset] = (log[offset] & !BANK) | flags | (bank << 2);
+}
+
+// FCEUX looks for game.cdl next to game.nes
+pub fn path(rom: &Path) -> PathBuf {
+    rom.with_extension("cdl")
+}
+
+pub fn save(path: &Path, prg: &[u8], chr: &[u8]) -> Result<(), String> {
+    fs::write(path, [prg, chr].concat()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Merges a saved log into prg and chr, so logging carries on where an earlier session stopped
+pub fn load(path: &Path, prg: &mut [u8], chr: &mut [u8]) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    if data.len() != prg.len() + chr.len() {
+        return Err(format!("{}: {} bytes, the ROM needs {}", path.display(), data.len(), prg.len() + chr.len()));
+    }
+
+    let (saved_prg, saved_chr) = data.split_at(prg.len());
+    prg.iter_mut().zip(saved_prg).for_each(|(flags, saved)| *flags |= saved);
+    chr.iter_mut().zip(saved_chr).for_each(|(flags, saved)| *flags |= saved);
+
+    Ok(())
+}
+
+// Share of the ROM logged so far
+pub struct Coverage {
+    pub code: usize,
+    pub data: usize,
+    pub prg: usize,
+    pub chr_rendered: usize,
+    pub chr_read: usize,
+    pub chr: usize,
+}
+
+impl Coverage {
+    pub fn new(prg: &[u8], chr: &[u8]) -> Self {
+        Coverage {
+            code: prg.iter().filter(|flags| *flags & CODE != 0).count(),
+            data: prg.iter().filter(|flags| *flags & DATA != 0).count(),
+            prg: prg.len(),
+            chr_rendered: chr.iter().filter(|flags| *flags & CHR_RENDERED != 0).count(),
+            chr_read: chr.iter().filter(|flags| *flags & CHR_READ != 0).count(),
+            chr: chr.len(),
+        }
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |count: usize, total: usize| if total == 0 { 0.0 } else { count as f64 * 100.0 / total as f64 };
+
+        write!(f, "Code {:.1}%, data {:.1}% of PRG", percent(self.code, self.prg), percent(self.data, self.prg))?;
+        if self.chr > 0 {
+            write!(f, ", CHR {:.1}% rendered, {:.1}% read", percent(self.chr_rendered, self.chr), percent(self.chr_read, self.chr))?;
+        }
+        // Without an APU nothing sets PCM_AUDIO
+        write!(f, " (DMC samples aren't logged)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::run_nes;
+
+    #[test]
+    fn code_data_and_indirect_accesses() {
+        let cpu = run_nes("
+                LDA table
+                LDA #$12
+                STA $10
+                LDA #$80
+                STA $11
+                LDY #$01
+                LDA ($10),Y
+                JMP (pointer)
+            table:
+                .byte $AA, $BB
+            pointer:
+                .word target
+            target:
+                NOP
+            ");
+        let log = &cpu.bus.prg_log;
+
+        // LDA table, three bytes of code
+        assert_eq!(&log[0x0000..0x0003], &[CODE; 3]);
+        // table ($8012) is read directly and through the pointer in $10
+        assert_eq!(log[0x0012], DATA);
+        assert_eq!(log[0x0013], DATA | INDIRECT_DATA);
+        // The pointer of the JMP is data, its destination code reached indirectly
+        assert_eq!(&log[0x0014..0x0016], &[DATA; 2]);
+        assert_eq!(log[0x0016], CODE | INDIRECT_CODE);
+        // The BRK after it never ran
+        assert_eq!(log[0x0017], 0x00);
+    }
+
+    #[test]
+    fn bank_bits() {
+        let mut log = [0; 2];
+        mark_prg(&mut log, 0, 0xE123, CODE);
+        mark_prg(&mut log, 1, 0xA000, DATA);
+        assert_eq!(log, [CODE | 0x0C, DATA | 0x04]);
+
+        // The bank is where the byte was seen last
+        mark_prg(&mut log, 0, 0x8123, DATA);
+        assert_eq!(log[0], CODE | DATA);
+    }
+
+    #[test]
+    fn saved_logs_are_merged() {
+        let path = std::env::temp_dir().join(format!("runes-cdl-{}.cdl", std::process::id()));
+
+        save(&path, &[CODE, 0x00, DATA], &[CHR_READ]).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), vec![CODE, 0x00, DATA, CHR_READ]);
+
+        let mut prg = [DATA, 0x00, 0x00];
+        let mut chr = [CHR_RENDERED];
+        load(&path, &mut prg, &mut chr).unwrap();
+        assert_eq!((prg, chr), ([CODE | DATA, 0x00, DATA], [CHR_RENDERED | CHR_READ]));
+
+        assert!(load(&path, &mut [0; 5], &mut []).is_err());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Coverage::new(&prg, &chr).to_string(), "Code 33.3%, data 66.7% of PRG, CHR 100.0% rendered, 100.0% read (DMC samples aren't logged)");
+    }
+}
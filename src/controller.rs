@@ -0,0 +1,393 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ppu::{PPU, HEIGHT, SYSTEM_PALLETE, WIDTH};
+
+// Pixels around the aim the Zapper's photodiode sees
+const ZAPPER_RADIUS: i32 = 2;
+// Scanlines the photodiode keeps responding after the beam lit a pixel
+const ZAPPER_SCANLINES: i32 = 20;
+// Luminance a pixel needs to register, white and the light grays but not the darker colors
+const ZAPPER_LUMINANCE: u32 = 0xA0;
+
+// Bits 16-23 of the Four Score's serial read of $4016 and $4017, first bit read in bit 0.
+// Games shifting the bits in from the top see $10 and $20.
+const FOUR_SCORE_SIGNATURES: [u8; 2] = [0x08, 0x04];
+
+// Buttons of the standard controller, in the order its shift register reports them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Button {
+    A,
+    B,
+    Select,
+    Start,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+pub const BUTTONS: [Button; 8] = [
+    Button::A, Button::B, Button::Select, Button::Start, Button::Up, Button::Down, Button::Left, Button::Right,
+];
+
+impl Button {
+    // Bit of the button in Joypad::buttons
+    pub fn mask(&self) -> u8 {
+        1 << *self as u8
+    }
+}
+
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// Standard controller: a 4021 shift register that keeps loading the buttons while the strobe is high
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Joypad {
+    pub buttons: u8, // Held down, a bit per Button
+    shift: u8,
+}
+
+impl Joypad {
+    fn load(&mut self) {
+        self.shift = self.buttons;
+    }
+
+    fn read(&mut self, strobe: bool) -> u8 {
+        let bit = self.peek(strobe);
+        if !strobe {
+            // Official controllers shift in 1s, every read after the 8th returns 1
+            self.shift = (self.shift >> 1) | 0x80;
+        }
+        bit
+    }
+
+    // While the strobe is high every read returns A
+    fn peek(&self, strobe: bool) -> u8 {
+        if strobe { self.buttons & 0x01 } else { self.shift & 0x01 }
+    }
+}
+
+// Light gun, its trigger on D4 and its photodiode on D3
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Zapper {
+    pub trigger: bool,
+    pub aim: Option<(u16, u16)>, // Pixel it points at, None when off the screen
+}
+
+impl Zapper {
+    fn peek(&self, ppu: &PPU) -> u8 {
+        let trigger = if self.trigger { 0x10 } else { 0x00 };
+        // D3 is low while the photodiode sees light
+        let light = if self.senses_light(ppu) { 0x00 } else { 0x08 };
+        trigger | light
+    }
+
+    // Whether a bright pixel near the aim was output during the last few scanlines. The CRT only glows for a
+    // moment after the beam passed, so what matters is what the PPU is drawing now, not the last finished frame.
+    pub fn senses_light(&self, ppu: &PPU) -> bool {
+        let Some((x, y)) = self.aim else { return false };
+        let (scanline, dot) = (ppu.scanline as i32, ppu.cycle as i32 - 1);
+
+        (-ZAPPER_RADIUS..=ZAPPER_RADIUS).any(|dy| (-ZAPPER_RADIUS..=ZAPPER_RADIUS).any(|dx| {
+            let (px, py) = (x as i32 + dx, y as i32 + dy);
+            let on_screen = (0..WIDTH as i32).contains(&px) && (0..HEIGHT as i32).contains(&py);
+            // Drawn already in this frame, recently enough to still glow
+            let lit = py < scanline || (py == scanline && px < dot);
+
+            on_screen && lit && scanline - py < ZAPPER_SCANLINES && luminance(ppu.frame_buffer[py as usize * WIDTH + px as usize]) >= ZAPPER_LUMINANCE
+        }))
+    }
+}
+
+fn luminance(color: u8) -> u32 {
+    let (r, g, b) = SYSTEM_PALLETE[color as usize];
+    (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000
+}
+
+// What is plugged into a controller port
+#[derive(Clone, Debug, PartialEq)]
+pub enum Device {
+    Empty,
+    Joypad(Joypad),
+    Zapper(Zapper),
+}
+
+impl Device {
+    // Data lines D0-D4 of a read from the port
+    fn read(&mut self, strobe: bool, ppu: &PPU) -> u8 {
+        match self {
+            Device::Joypad(joypad) => joypad.read(strobe),
+            _ => self.peek(strobe, ppu),
+        }
+    }
+
+    fn peek(&self, strobe: bool, ppu: &PPU) -> u8 {
+        match self {
+            Device::Empty => 0,
+            Device::Joypad(joypad) => joypad.peek(strobe),
+            Device::Zapper(zapper) => zapper.peek(ppu),
+        }
+    }
+}
+
+// What connects players 3 and 4
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Adapter {
+    #[default]
+    None,
+    // NES Four Score: both ports read 24 bits, the player of the port, the player behind it and a signature
+    FourScore,
+    // Famicom 4-player adapter on the expansion port: players 3 and 4 read on D1 alongside players 1 and 2
+    Famicom,
+}
+
+pub const ADAPTERS: [Adapter; 3] = [Adapter::None, Adapter::FourScore, Adapter::Famicom];
+
+impl FromStr for Adapter {
+    type Err = String;
+
+    // none, four-score or famicom
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Adapter::None),
+            "four-score" => Ok(Adapter::FourScore),
+            "famicom" => Ok(Adapter::Famicom),
+            _ => Err(format!("Unknown adapter: {} (expected none, four-score or famicom)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Adapter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Adapter::None => write!(f, "None"),
+            Adapter::FourScore => write!(f, "Four Score"),
+            Adapter::Famicom => write!(f, "Famicom 4-player adapter"),
+        }
+    }
+}
+
+// The two controller ports behind $4016 and $4017
+#[derive(Clone, Debug, PartialEq)]
+pub struct Controllers {
+    pub ports: [Device; 2],
+    pub adapter: Adapter,
+    pub expansion: [Joypad; 2], // Players 3 and 4, only read with an adapter
+    reads: [u8; 2], // Bits of the Four Score read from each port since the strobe
+    strobe: bool, // Bit 0 of the last write to $4016
+}
+
+impl Default for Controllers {
+    fn default() -> Self {
+        Controllers {
+            ports: [Device::Joypad(Joypad::default()), Device::Joypad(Joypad::default())],
+            adapter: Adapter::None,
+            expansion: Default::default(),
+            reads: [0; 2],
+            strobe: false,
+        }
+    }
+}
+
+impl Controllers {
+    // $4016, bit 0 is the strobe of both ports
+    pub fn write(&mut self, data: u8) {
+        self.strobe = data & 0x01 != 0;
+        if self.strobe {
+            let expansion = self.expansion.iter_mut();
+            let ports = self.ports.iter_mut().filter_map(|port| match port {
+                Device::Joypad(joypad) => Some(joypad),
+                _ => None,
+            });
+            for joypad in ports.chain(expansion) {
+                joypad.load();
+            }
+            self.reads = [0; 2];
+        }
+    }
+
+    // $4016 for port 0, $4017 for port 1. Only the low bits are driven, the rest is open bus.
+    // The Zapper looks at what the PPU is drawing.
+    pub fn read(&mut self, port: usize, ppu: &PPU) -> u8 {
+        match self.adapter {
+            Adapter::None => self.ports[port].read(self.strobe, ppu),
+            Adapter::FourScore => {
+                let bit = self.peek(port, ppu);
+                if !self.strobe {
+                    match self.reads[port] {
+                        0..=7 => { self.ports[port].read(false, ppu); },
+                        8..=15 => { self.expansion[port].read(false); },
+                        _ => {},
+                    }
+                    self.reads[port] = (self.reads[port] + 1).min(24);
+                }
+                bit
+            },
+            Adapter::Famicom => self.ports[port].read(self.strobe, ppu) | self.expansion[port].read(self.strobe) << 1,
+        }
+    }
+
+    pub fn peek(&self, port: usize, ppu: &PPU) -> u8 {
+        match self.adapter {
+            Adapter::None => self.ports[port].peek(self.strobe, ppu),
+            // While the strobe is high the Four Score keeps returning A of the player in the port
+            Adapter::FourScore if self.strobe => self.ports[port].peek(true, ppu) & 0x01,
+            Adapter::FourScore => match self.reads[port] {
+                0..=7 => self.ports[port].peek(false, ppu) & 0x01,
+                8..=15 => self.expansion[port].peek(false),
+                bit @ 16..=23 => (FOUR_SCORE_SIGNATURES[port] >> (bit - 16)) & 0x01,
+                _ => 1,
+            },
+            Adapter::Famicom => self.ports[port].peek(self.strobe, ppu) | self.expansion[port].peek(self.strobe) << 1,
+        }
+    }
+
+    pub fn zapper_mut(&mut self) -> Option<&mut Zapper> {
+        self.ports.iter_mut().find_map(|port| match port {
+            Device::Zapper(zapper) => Some(zapper),
+            _ => None,
+        })
+    }
+
+    // Buttons player is holding down, for the controllers that have a player. Players 3 and 4 are behind the adapter.
+    pub fn set_buttons(&mut self, player: usize, buttons: u8) {
+        match player {
+            0 | 1 => if let Device::Joypad(joypad) = &mut self.ports[player] {
+                joypad.buttons = buttons;
+            },
+            2 | 3 => self.expansion[player - 2].buttons = buttons,
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::run_nes;
+    use crate::cartridge::Mirroring;
+
+    #[test]
+    fn shift_register() {
+        let ppu = PPU::new(vec![], Mirroring::Horizontal);
+        let mut controllers = Controllers::default();
+        controllers.set_buttons(0, Button::A.mask() | Button::Start.mask() | Button::Right.mask());
+        controllers.set_buttons(1, Button::B.mask());
+
+        // Strobe high, A over and over
+        controllers.write(0x01);
+        assert_eq!((0..3).map(|_| controllers.read(0, &ppu)).collect::<Vec<_>>(), vec![1, 1, 1]);
+
+        controllers.write(0x00);
+        let bits: Vec<u8> = (0..10).map(|_| controllers.read(0, &ppu)).collect();
+        assert_eq!(bits, vec![1, 0, 0, 1, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(controllers.peek(1, &ppu), 0);
+        assert_eq!(controllers.read(1, &ppu), 0);
+        assert_eq!(controllers.read(1, &ppu), 1);
+
+        // Buttons pressed after the strobe went low only show up after the next one
+        controllers.set_buttons(0, Button::B.mask());
+        assert_eq!(controllers.read(0, &ppu), 1);
+        controllers.ports[1] = Device::Empty;
+        assert_eq!(controllers.read(1, &ppu), 0);
+    }
+
+    #[test]
+    fn reads_through_the_bus() {
+        let cpu = run_nes("
+                LDA #$01
+                STA $4016
+                LDA #$00
+                STA $4016
+                LDX #$00
+            read:
+                LDA $4016
+                STA $00,X
+                INX
+                CPX #$09
+                BNE read
+            ");
+
+        // No buttons held, the upper bits are the high byte of the address left on the bus
+        assert_eq!(&cpu.bus.cpu_vram[0x00..0x09], &[0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x41]);
+    }
+
+    #[test]
+    fn zapper_light_follows_the_beam() {
+        let mut ppu = PPU::new(vec![], Mirroring::Horizontal);
+        let mut zapper = Zapper { trigger: true, aim: Some((100, 50)) };
+
+        // A white box around (100, 50) in the frame buffer
+        for y in 45..55 {
+            ppu.frame_buffer[y * WIDTH + 95..y * WIDTH + 105].fill(0x30);
+        }
+
+        // Not drawn yet in this frame
+        ppu.scanline = 40;
+        assert_eq!(zapper.peek(&ppu), 0x10 | 0x08);
+
+        // Just drawn, then too long ago to still glow
+        ppu.scanline = 51;
+        assert_eq!(zapper.peek(&ppu), 0x10);
+        ppu.scanline = 80;
+        assert!(!zapper.senses_light(&ppu));
+
+        // Dark pixels don't register, and neither does aiming off the screen
+        ppu.scanline = 51;
+        for y in 45..55 {
+            ppu.frame_buffer[y * WIDTH + 95..y * WIDTH + 105].fill(0x0F);
+        }
+        assert!(!zapper.senses_light(&ppu));
+        zapper.aim = None;
+        zapper.trigger = false;
+        assert_eq!(zapper.peek(&ppu), 0x08);
+    }
+
+    #[test]
+    fn four_score() {
+        let ppu = PPU::new(vec![], Mirroring::Horizontal);
+        let mut controllers = Controllers { adapter: Adapter::FourScore, ..Controllers::default() };
+        controllers.set_buttons(0, Button::A.mask());
+        controllers.set_buttons(1, Button::Right.mask());
+        controllers.set_buttons(2, Button::Start.mask());
+        controllers.set_buttons(3, Button::B.mask() | Button::Up.mask());
+
+        controllers.write(0x01);
+        assert_eq!(controllers.read(0, &ppu), 1);
+        controllers.write(0x00);
+
+        let mut bits = |port| -> Vec<u8> { (0..26).map(|_| controllers.read(port, &ppu)).collect() };
+        let (port_1, port_2) = (bits(0), bits(1));
+
+        // Player 1, player 3, the signature, then 1s
+        assert_eq!(&port_1[0..8], &[1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&port_1[8..16], &[0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(&port_1[16..], &[0, 0, 0, 1, 0, 0, 0, 0, 1, 1]);
+        // Player 2, player 4 and the other signature
+        assert_eq!(&port_2[0..8], &[0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(&port_2[8..16], &[0, 1, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(&port_2[16..], &[0, 0, 1, 0, 0, 0, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn famicom_adapter() {
+        let ppu = PPU::new(vec![], Mirroring::Horizontal);
+        let mut controllers = Controllers { adapter: Adapter::Famicom, ..Controllers::default() };
+        controllers.set_buttons(0, Button::A.mask());
+        controllers.set_buttons(2, Button::A.mask() | Button::B.mask());
+        controllers.set_buttons(3, Button::Select.mask());
+
+        controllers.write(0x01);
+        controllers.write(0x00);
+
+        // Players 3 and 4 on D1, next to players 1 and 2 on D0
+        assert_eq!((0..3).map(|_| controllers.read(0, &ppu)).collect::<Vec<_>>(), vec![0x03, 0x02, 0x00]);
+        assert_eq!((0..3).map(|_| controllers.read(1, &ppu)).collect::<Vec<_>>(), vec![0x00, 0x00, 0x02]);
+        assert_eq!("four-score".parse(), Ok(Adapter::FourScore));
+        assert!("multitap".parse::<Adapter>().is_err());
+    }
+}
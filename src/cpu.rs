@@ -1,21 +1,76 @@
-use crate::opcodes::{references, Opcode};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::opcodes::{references, Instruction, Opcode};
 use crate::bus::Bus;
 use crate::cartridge::Cartridge;
+use crate::memory::Memory;
+use crate::call_stack::{CallStack, Frame, FrameKind};
+use crate::cdl;
 
 enum StatusFlag {
     C = (1 << 0), // Carry Bit
     Z = (1 << 1), // Zero
     I = (1 << 2), // Disable Interrupts
-    D = (1 << 3), // Decimal Mode (unused in this implementation)
+    D = (1 << 3), // Decimal Mode (ignored by the 2A03)
     B = (1 << 4), // Break
     U = (1 << 5), // Unused
     V = (1 << 6), // Overflow
     N = (1 << 7), // Negative
 }
 
+// The 2A03 in the NES is an NMOS 6502 with the decimal mode cut out
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Variant {
+    #[default]
+    Ricoh2A03,
+    Nmos6502,
+    Cmos65C02,
+}
+
+impl Variant {
+    pub fn instructions(&self) -> &'static [Instruction; 256] {
+        match self {
+            Variant::Ricoh2A03 | Variant::Nmos6502 => &references::INSTRUCTION_LOOKUP,
+            Variant::Cmos65C02 => &references::CMOS_INSTRUCTION_LOOKUP,
+        }
+    }
+
+    pub fn has_decimal_mode(&self) -> bool {
+        *self != Variant::Ricoh2A03
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "2a03" => Ok(Variant::Ricoh2A03),
+            "6502" => Ok(Variant::Nmos6502),
+            "65c02" => Ok(Variant::Cmos65C02),
+            _ => Err(format!("unknown CPU variant '{}', expected 2a03, 6502 or 65c02", s)),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Ricoh2A03 => write!(f, "2A03"),
+            Variant::Nmos6502 => write!(f, "6502"),
+            Variant::Cmos65C02 => write!(f, "65C02"),
+        }
+    }
+}
+
+// Magic constant ORed into A by the unstable XAA and LXA opcodes, it varies between chips
+const UNSTABLE_MAGIC: u8 = 0xEE;
+
 #[derive(PartialEq, Debug)]
 pub enum AddressingMode {
     IMP, // Implied
+    ACC, // Accumulator
     IMM, // Immediate
     ZP0, // Zero Page
     ZPX, // Zero Page with X Offset
@@ -27,9 +82,12 @@ pub enum AddressingMode {
     IND, // Indirect
     IZX, // Indirect with X Offset
     IZY, // Indirect with Y Offset
+    IZP, // Zero Page Indirect (65C02)
+    IAX, // Absolute Indirect with X Offset (65C02)
 }
 
-pub struct CPU {
+// The CPU is generic over the memory it is attached to, the NES Bus being the default
+pub struct CPU<M: Memory = Bus> {
     pub accumulator: u8, // Accumulator Register
     pub x_register: u8, // X Register
     pub y_register: u8, // Y Register
@@ -43,22 +101,39 @@ pub struct CPU {
     pub addr_abs: u16, // All used memory addresses end up in here
     pub addr_rel: u16, // Represents absolute address following a branch
     pub opcode: u8, // Instruction opcode is fetched here
-    pub cycles: u8, // Counts how many cycles the instruction has remaining
-    
-    pub bus: Bus,
+    pub cycles: u16, // Number of cycles the last instruction took, a sprite DMA it started included
+    pub variant: Variant,
+    pub jammed: Option<u16>, // Address of the JAM opcode that halted the CPU, only a reset gets it going again
+    pub interrupted: Option<u16>, // Vector of the interrupt taken at the end of the last step
+    pub call_stack: CallStack,
 
-    pub system_clock_counter: u32,
+    pub nmi_pending: bool, // Latched when the NMI line is asserted, cleared when the NMI is taken
+    run_interrupt: bool, // Whether an interrupt was seen on the current cycle
+    prev_run_interrupt: bool, // Whether an interrupt was seen on the previous cycle
+
+    pub bus: M,
+
+    pub system_clock_counter: u64,
 }
-    
+
 impl CPU {
+    // A powered on NES with the cartridge inserted, about to run the first instruction at the reset vector
     pub fn new(cartridge: Cartridge) -> Self {
+        let mut cpu = CPU::with_memory(Bus::new(cartridge));
+        cpu.power_on();
+        cpu
+    }
+}
+
+impl<M: Memory> CPU<M> {
+    pub fn with_memory(bus: M) -> Self {
         CPU {
             accumulator: 0x00,
             x_register: 0x00,
             y_register: 0x00,
             stack_pointer: 0x00,
             program_counter: 0x0000,
-            status: 0x00,
+            status: StatusFlag::U as u8 | StatusFlag::I as u8,
 
             fetched: 0x00,
 
@@ -66,133 +141,253 @@ impl CPU {
             addr_rel: 0x0000,
             opcode: 0x00,
             cycles: 0x00,
+            variant: Variant::default(),
+            jammed: None,
+            interrupted: None,
+            call_stack: CallStack::default(),
 
-            bus: Bus::new(cartridge),
+            nmi_pending: false,
+            run_interrupt: false,
+            prev_run_interrupt: false,
+
+            bus,
 
             system_clock_counter: 0,
-        } 
+        }
     }
 
-    pub fn read(&mut self, addr: u16, _b_read_only: bool) -> u8 {
-        self.bus.mem_read(addr)
+    // Every bus access takes one CPU cycle, read only accesses peek at memory without using one
+    pub fn read(&mut self, addr: u16, b_read_only: bool) -> u8 {
+        if b_read_only {
+            self.peek(addr)
+        } else {
+            self.tick();
+            self.bus.mem_read(addr)
+        }
+    }
+
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.bus.mem_peek(addr)
     }
 
     pub fn write(&mut self, addr: u16, data: u8) {
+        self.tick();
         self.bus.mem_write(addr, data);
     }
 
-    pub fn clock(&mut self) {
-
-        self.bus.ppu.clock();
-
-        // CPU runs 1/3 as fast as PPU
-
-        if self.system_clock_counter % 3 == 0 {
-            if self.cycles == 0 {
-                self.opcode = self.read(self.program_counter, false);
-                self.program_counter += 1;
-
-                let operate = &references::INSTRUCTION_LOOKUP[self.opcode as usize].operate;
-                let addressing_mode = &references::INSTRUCTION_LOOKUP[self.opcode as usize].addrmode;
-
-                self.cycles = references::INSTRUCTION_LOOKUP[self.opcode as usize].cycles;
-
-
-                let additional_cycle1: u8 = match addressing_mode {
-                    AddressingMode::IMP => self.imp(),
-                    AddressingMode::IMM => self.imm(),
-                    AddressingMode::ZP0 => self.zp0(),
-                    AddressingMode::ZPX => self.zpx(),
-                    AddressingMode::ZPY => self.zpy(),
-                    AddressingMode::REL => self.rel(),
-                    AddressingMode::ABS => self.abs(),
-                    AddressingMode::ABX => self.abx(),
-                    AddressingMode::ABY => self.aby(),
-                    AddressingMode::IND => self.ind(),
-                    AddressingMode::IZX => self.izx(),
-                    AddressingMode::IZY => self.izy(),
-                };
-
-                let additional_cycle2: u8 = match operate {
-                    Opcode::ADC => self.adc(),
-                    Opcode::AND => self.and(),
-                    Opcode::ASL => self.asl(),
-                    Opcode::BCC => self.bcc(),
-                    Opcode::BCS => self.bcs(),
-                    Opcode::BEQ => self.beq(),
-                    Opcode::BIT => self.bit(),
-                    Opcode::BMI => self.bmi(),
-                    Opcode::BNE => self.bne(),
-                    Opcode::BPL => self.bpl(),
-                    Opcode::BRK => self.brk(),
-                    Opcode::BVC => self.bvc(),
-                    Opcode::BVS => self.bvs(),
-                    Opcode::CLC => self.clc(),
-                    Opcode::CLD => self.cld(),
-                    Opcode::CLI => self.cli(),
-                    Opcode::CLV => self.clv(),
-                    Opcode::CMP => self.cmp(),
-                    Opcode::CPX => self.cpx(),
-                    Opcode::CPY => self.cpy(),
-                    Opcode::DEC => self.dec(),
-                    Opcode::DEX => self.dex(),
-                    Opcode::DEY => self.dey(),
-                    Opcode::EOR => self.eor(),
-                    Opcode::INC => self.inc(),
-                    Opcode::INX => self.inx(),
-                    Opcode::INY => self.iny(),
-                    Opcode::JMP => self.jmp(),
-                    Opcode::JSR => self.jsr(),
-                    Opcode::LDA => self.lda(),
-                    Opcode::LDX => self.ldx(),
-                    Opcode::LDY => self.ldy(),
-                    Opcode::LSR => self.lsr(),
-                    Opcode::NOP => self.nop(),
-Opcode::ORA => self.ora(),
-Opcode::PHA => self.pha(),
-                    Opcode::PHP => self.php(),
-Opcode::PLA => self.pla(),
-                    Opcode::PLP => self.plp(),
-                    Opcode::ROL => self.rol(),
-                    Opcode::ROR => self.ror(),
-                    Opcode::RTI => self.rti(),
-                    Opcode::RTS => self.rts(),
-                    Opcode::SBC => self.sbc(),
-                    Opcode::SEC => self.sec(),
-                    Opcode::SED => self.sed(),
-                    Opcode::SEI => self.sei(),
-                    Opcode::STA => self.sta(),
-                    Opcode::STX => self.stx(),
-                    Opcode::STY => self.sty(),
-                    Opcode::TAX => self.tax(),
-                    Opcode::TAY => self.tay(),
-                    Opcode::TSX => self.tsx(),
-                    Opcode::TXA => self.txa(),
-                    Opcode::TXS => self.txs(),
-                    Opcode::TYA => self.tya(),
-                    Opcode::XXX => self.xxx(),
-                };
-
-
-                self.cycles += additional_cycle1 & additional_cycle2;
-
-                self.set_flag(StatusFlag::U, true);
-            }
+    // CPU runs 1/3 as fast as PPU, so the devices advance three ticks for every CPU cycle
+    fn tick(&mut self) {
+        for _ in 0..3 {
+            self.bus.tick();
+        }
+        self.system_clock_counter += 3;
+
+        // The interrupt lines are sampled every cycle, but an instruction only acts on what was seen
+        // up to its second to last cycle. That is why CLI, SEI and PLP only take effect one instruction later.
+        if self.bus.poll_nmi() {
+            self.nmi_pending = true;
+        }
+        self.prev_run_interrupt = self.run_interrupt;
+        self.run_interrupt = self.nmi_pending || (self.bus.irq() && self.get_flag(StatusFlag::I) == 0);
+    }
+
+    // Executes one instruction cycle by cycle, followed by the interrupt sequence if one was polled meanwhile
+    pub fn step(&mut self) {
+        let start = self.system_clock_counter;
+        self.interrupted = None;
 
-            self.cycles -= 1;
+        // A jammed CPU stops fetching and ignores interrupts, the rest of the system keeps running
+        if self.jammed.is_some() {
+            self.tick();
+            self.cycles = 1;
+            return;
         }
 
-        // When entering vblank, the PPU will set the NMI flag 
-        if self.bus.ppu.nmi {
-            self.bus.ppu.nmi = false;
-            self.nmi();
+        let pc = self.program_counter;
+        let stack_pointer = self.stack_pointer;
+
+        self.opcode = self.read(self.program_counter, false);
+        self.program_counter = self.program_counter.wrapping_add(1);
+
+        let instruction = self.instruction();
+        let operate = &instruction.operate;
+
+        match instruction.addrmode {
+            // JSR pushes the return address in between reading the two bytes of its operand
+            _ if *operate == Opcode::JSR => {},
+            // The single byte NOPs of the 65C02 are over after the opcode fetch
+            _ if instruction.cycles == 1 => {},
+            AddressingMode::IMP | AddressingMode::ACC => self.imp(),
+            AddressingMode::IMM => self.imm(),
+            AddressingMode::ZP0 => self.zp0(),
+            AddressingMode::ZPX => self.zpx(),
+            AddressingMode::ZPY => self.zpy(),
+            AddressingMode::REL => self.rel(),
+            AddressingMode::ABS => self.abs(),
+            AddressingMode::ABX => self.abx(),
+            AddressingMode::ABY => self.aby(),
+            AddressingMode::IND => self.ind(),
+            AddressingMode::IZX => self.izx(),
+            AddressingMode::IZY => self.izy(),
+            AddressingMode::IZP => self.izp(),
+            AddressingMode::IAX => self.iax(),
         }
 
-        self.system_clock_counter += 1;
+        match operate {
+            Opcode::ADC => self.adc(),
+            Opcode::AND => self.and(),
+            Opcode::ASL => self.asl(),
+            Opcode::BCC => self.bcc(),
+            Opcode::BCS => self.bcs(),
+            Opcode::BEQ => self.beq(),
+            Opcode::BIT => self.bit(),
+            Opcode::BMI => self.bmi(),
+            Opcode::BNE => self.bne(),
+            Opcode::BPL => self.bpl(),
+            Opcode::BRK => self.brk(),
+            Opcode::BVC => self.bvc(),
+            Opcode::BVS => self.bvs(),
+            Opcode::CLC => self.clc(),
+            Opcode::CLD => self.cld(),
+            Opcode::CLI => self.cli(),
+            Opcode::CLV => self.clv(),
+            Opcode::CMP => self.cmp(),
+            Opcode::CPX => self.cpx(),
+            Opcode::CPY => self.cpy(),
+            Opcode::DEC => self.dec(),
+            Opcode::DEX => self.dex(),
+            Opcode::DEY => self.dey(),
+            Opcode::EOR => self.eor(),
+            Opcode::INC => self.inc(),
+            Opcode::INX => self.inx(),
+            Opcode::INY => self.iny(),
+            Opcode::JMP => self.jmp(),
+            Opcode::JSR => self.jsr(),
+            Opcode::LDA => self.lda(),
+            Opcode::LDX => self.ldx(),
+            Opcode::LDY => self.ldy(),
+            Opcode::LSR => self.lsr(),
+            Opcode::NOP => self.nop(),
+            Opcode::ORA => self.ora(),
+            Opcode::PHA => self.pha(),
+            Opcode::PHP => self.php(),
+            Opcode::PLA => self.pla(),
+            Opcode::PLP => self.plp(),
+            Opcode::ROL => self.rol(),
+            Opcode::ROR => self.ror(),
+            Opcode::RTI => self.rti(),
+            Opcode::RTS => self.rts(),
+            Opcode::SBC => self.sbc(),
+            Opcode::SEC => self.sec(),
+            Opcode::SED => self.sed(),
+            Opcode::SEI => self.sei(),
+            Opcode::STA => self.sta(),
+            Opcode::STX => self.stx(),
+            Opcode::STY => self.sty(),
+            Opcode::TAX => self.tax(),
+            Opcode::TAY => self.tay(),
+            Opcode::TSX => self.tsx(),
+            Opcode::TXA => self.txa(),
+            Opcode::TXS => self.txs(),
+            Opcode::TYA => self.tya(),
+            Opcode::ALR => self.alr(),
+            Opcode::ANC => self.anc(),
+            Opcode::ARR => self.arr(),
+            Opcode::AXS => self.axs(),
+            Opcode::DCP => self.dcp(),
+            Opcode::ISB => self.isb(),
+            Opcode::LAS => self.las(),
+            Opcode::LAX => self.lax(),
+            Opcode::RLA => self.rla(),
+            Opcode::RRA => self.rra(),
+            Opcode::SAX => self.sax(),
+            Opcode::SLO => self.slo(),
+            Opcode::SRE => self.sre(),
+            Opcode::AHX => self.ahx(),
+            Opcode::LXA => self.lxa(),
+            Opcode::SHX => self.shx(),
+            Opcode::SHY => self.shy(),
+            Opcode::TAS => self.tas(),
+            Opcode::XAA => self.xaa(),
+            Opcode::BRA => self.bra(),
+            Opcode::PHX => self.phx(),
+            Opcode::PHY => self.phy(),
+            Opcode::PLX => self.plx(),
+            Opcode::PLY => self.ply(),
+            Opcode::STZ => self.stz(),
+            Opcode::TRB => self.trb(),
+            Opcode::TSB => self.tsb(),
+            Opcode::XXX => self.xxx(),
+        }
+
+        self.set_flag(StatusFlag::U, true);
+
+        self.track_call(pc, stack_pointer);
+        self.log_code(pc, instruction);
+
+        if let Some(page) = self.bus.take_oam_dma() {
+            self.oam_dma(page);
+        }
+
+        self.cycles = ((self.system_clock_counter - start) / 3) as u16;
+
+        if self.prev_run_interrupt && self.jammed.is_none() {
+            self.interrupt();
+        }
+    }
+
+    // Copies a page to OAM through $2004, the CPU is halted for 513 cycles, 514 when the DMA starts on an odd cycle
+    fn oam_dma(&mut self, page: u8) {
+        // One cycle waiting for the write to finish, another one so the reads land on even cycles
+        self.tick();
+        if self.total_cycles() % 2 == 1 {
+            self.tick();
+        }
+
+        for offset in 0..=0xFF {
+            let data = self.read((page as u16) << 8 | offset, false);
+            self.write(0x2004, data);
+        }
+    }
+
+    // Keeps the shadow call stack in line with the instruction at pc that just ran
+    fn track_call(&mut self, pc: u16, stack_pointer: u8) {
+        let kind = match self.instruction().operate {
+            Opcode::JSR => FrameKind::Jsr,
+            Opcode::BRK => FrameKind::Brk,
+            Opcode::RTS => return self.call_stack.pop(pc, stack_pointer, false, self.program_counter),
+            Opcode::RTI => return self.call_stack.pop(pc, stack_pointer, true, self.program_counter),
+            Opcode::TXS => return self.call_stack.unwind(pc, self.stack_pointer),
+            _ => return,
+        };
+
+        let frame = Frame { kind, source: pc, target: self.program_counter, stack_pointer: self.stack_pointer };
+        self.call_stack.push(stack_pointer, frame);
+    }
+
+    // The bytes of the instruction at pc are code, a JMP through a pointer lands on indirectly reached code
+    fn log_code(&mut self, pc: u16, instruction: &Instruction) {
+        for offset in 0..instruction.length() {
+            self.bus.log_code_data(pc.wrapping_add(offset), cdl::CODE);
+        }
+
+        if instruction.operate == Opcode::JMP && matches!(instruction.addrmode, AddressingMode::IND | AddressingMode::IAX) {
+            self.bus.log_code_data(self.program_counter, cdl::INDIRECT_CODE);
+        }
+    }
+
+    // The instruction the current opcode decodes to on this variant
+    pub fn instruction(&self) -> &'static Instruction {
+        &self.variant.instructions()[self.opcode as usize]
+    }
+
+    // Number of CPU cycles executed so far
+    pub fn total_cycles(&self) -> u64 {
+        self.system_clock_counter / 3
     }
 }
 
-impl CPU {
+impl<M: Memory> CPU<M> {
     // Flags Functions
     fn set_flag(&mut self, flag: StatusFlag, value: bool) {
         if value {
@@ -210,770 +405,1809 @@ impl CPU {
         }
     }
 
+    // Stack Functions
+    // The stack lives in page 1 and grows downwards
+
+    fn push(&mut self, data: u8) {
+        self.write(0x0100 + self.stack_pointer as u16, data);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+    }
+
+    fn pull(&mut self) -> u8 {
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        self.read(0x0100 + self.stack_pointer as u16, false)
+    }
+
+    // The cycle spent before pulling, the CPU reads the stack while it increments the pointer
+    fn peek_stack(&mut self) {
+        self.read(0x0100 + self.stack_pointer as u16, false);
+    }
+
     // Addressing Modes
-    // Returned integer is the additional number of cycles required for the Instruction
+    // Each mode performs the bus accesses needed to find the operand's address
 
-    fn imp(&mut self) -> u8{
+    fn imp(&mut self) {
+        // Single byte instructions still read the next byte, and throw it away
+        self.read(self.program_counter, false);
         // Some instructions use the accumulator's value as operand
         self.fetched = self.accumulator;
-        0
     }
 
-    fn imm(&mut self) -> u8 {
+    fn imm(&mut self) {
         self.addr_abs = self.program_counter;
-        self.program_counter += 1;
-        0
+        self.program_counter = self.program_counter.wrapping_add(1);
     }
 
-    fn zp0(&mut self) -> u8 {
+    fn zp0(&mut self) {
         self.addr_abs = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
-        self.addr_abs &= 0x00FF;
-        0
+        self.program_counter = self.program_counter.wrapping_add(1);
     }
 
-    fn zpx(&mut self) -> u8 {
-        self.addr_abs = (self.read(self.program_counter, false) + self.x_register) as u16;
-        self.program_counter += 1;
-        self.addr_abs &= 0x00FF;
-        0
-    }
+    fn zpx(&mut self) {
+        let t = self.read(self.program_counter, false);
+        self.program_counter = self.program_counter.wrapping_add(1);
 
-    fn zpy(&mut self) -> u8 {
-        self.addr_abs = (self.read(self.program_counter, false) + self.y_register) as u16;
-        self.program_counter += 1;
-        self.addr_abs &= 0x00FF;
-        0
+        // The base address is read while the index is added
+        self.read(t as u16, false);
+        self.addr_abs = t.wrapping_add(self.x_register) as u16;
     }
 
-    fn abs(&mut self) -> u8 {
-        //6502 stores memory address in little endian format
-        let lo = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+    fn zpy(&mut self) {
+        let t = self.read(self.program_counter, false);
+        self.program_counter = self.program_counter.wrapping_add(1);
 
-        let hi = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
-
-        self.addr_abs = (hi << 8) | lo;
-        0
+        self.read(t as u16, false);
+        self.addr_abs = t.wrapping_add(self.y_register) as u16;
     }
 
-    fn abx(&mut self) -> u8 {
+    fn abs(&mut self) {
+        //6502 stores memory address in little endian format
         let lo = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+        self.program_counter = self.program_counter.wrapping_add(1);
 
         let hi = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+        self.program_counter = self.program_counter.wrapping_add(1);
 
         self.addr_abs = (hi << 8) | lo;
-        self.addr_abs += self.x_register as u16;
-
-        // If the addition of the offset causes a change in the high byte, an additional cycle is required
-        if (self.addr_abs & 0xFF00) != (hi << 8) {
-            1
-        } else {
-            0
-        }
     }
 
-    fn aby(&mut self) -> u8 {
-        let lo = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
-
-        let hi = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
-
-        self.addr_abs = (hi << 8) | lo;
-        self.addr_abs += self.y_register as u16;
+    fn abx(&mut self) {
+        self.abs();
+        self.index(self.addr_abs, self.x_register);
+    }
 
-        // If the addition of the offset causes a change in the high byte, an additional cycle is required
-        if (self.addr_abs & 0xFF00) != (hi << 8) {
-            1
-        } else {
-            0
-        }
+    fn aby(&mut self) {
+        self.abs();
+        self.index(self.addr_abs, self.y_register);
     }
 
-    fn ind(&mut self) -> u8 {
+    fn ind(&mut self) {
         let ptr_lo = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+        self.program_counter = self.program_counter.wrapping_add(1);
 
         let ptr_hi = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+        self.program_counter = self.program_counter.wrapping_add(1);
 
         let ptr = (ptr_hi << 8) | ptr_lo;
 
+        if self.variant == Variant::Cmos65C02 {
+            // The 65C02 fixed the page wrap below, at the cost of a cycle
+            self.read(self.program_counter.wrapping_sub(1), false);
+            self.addr_abs = self.read_pointer(ptr, ptr.wrapping_add(1));
+            return;
+        }
+
         // 6502 bug where if the low byte of the supplied address is 0xFF, the high byte is fetched from the low byte of the supplied address
         // This is added for bug for bug compatibility
-        if ptr_lo == 0x00FF {
-            self.addr_abs = (self.read(ptr & 0xFF00, false) as u16) << 8 | self.read(ptr, false) as u16;
-        } else {
-            self.addr_abs = (self.read(ptr + 1, false) as u16) << 8 | self.read(ptr, false) as u16;
-        }
+        self.addr_abs = self.read_pointer(ptr, (ptr & 0xFF00) | (ptr.wrapping_add(1) & 0x00FF));
+    }
+
+    // Reads the target of an indirect JMP, the pointer itself is data
+    fn read_pointer(&mut self, lo_addr: u16, hi_addr: u16) -> u16 {
+        let lo = self.read(lo_addr, false) as u16;
+        let hi = self.read(hi_addr, false) as u16;
 
-        0
+        self.bus.log_code_data(lo_addr, cdl::DATA);
+        self.bus.log_code_data(hi_addr, cdl::DATA);
+
+        (hi << 8) | lo
     }
 
-    fn izx(&mut self) -> u8 {
-        let t = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+    fn izp(&mut self) {
+        let t = self.read(self.program_counter, false);
+        self.program_counter = self.program_counter.wrapping_add(1);
 
-        let lo = self.read((t + self.x_register as u16) & 0x00FF, false) as u16;
-        let hi = self.read((t + self.x_register as u16 + 1) & 0x00FF, false) as u16;
+        let lo = self.read(t as u16, false) as u16;
+        let hi = self.read(t.wrapping_add(1) as u16, false) as u16;
 
         self.addr_abs = (hi << 8) | lo;
-        0
     }
 
-    fn izy(&mut self) -> u8 {
-        let t = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+    fn iax(&mut self) {
+        self.abs();
+
+        // The index is added to the pointer while the last operand byte is read again
+        self.read(self.program_counter.wrapping_sub(1), false);
+        let ptr = self.addr_abs.wrapping_add(self.x_register as u16);
+
+        self.addr_abs = self.read_pointer(ptr, ptr.wrapping_add(1));
+    }
 
-        let lo = self.read(t & 0x00FF, false) as u16;
-        let hi = self.read((t + 1) & 0x00FF, false) as u16;
+    fn izx(&mut self) {
+        let t = self.read(self.program_counter, false);
+        self.program_counter = self.program_counter.wrapping_add(1);
+
+        self.read(t as u16, false);
+        let lo = self.read(t.wrapping_add(self.x_register) as u16, false) as u16;
+        let hi = self.read(t.wrapping_add(self.x_register).wrapping_add(1) as u16, false) as u16;
 
         self.addr_abs = (hi << 8) | lo;
-        self.addr_abs += self.y_register as u16;
+    }
 
-        // If the addition of the offset causes a change in the high byte, an additional cycle is required
-        if (self.addr_abs & 0xFF00) != (hi << 8) {
-            1
-        } else {
-            0
-        }
+    fn izy(&mut self) {
+        let t = self.read(self.program_counter, false);
+        self.program_counter = self.program_counter.wrapping_add(1);
+
+        let lo = self.read(t as u16, false) as u16;
+        let hi = self.read(t.wrapping_add(1) as u16, false) as u16;
+
+        self.index((hi << 8) | lo, self.y_register);
     }
 
-    fn rel(&mut self) -> u8 {
+    fn rel(&mut self) {
         self.addr_rel = self.read(self.program_counter, false) as u16;
-        self.program_counter += 1;
+        self.program_counter = self.program_counter.wrapping_add(1);
 
         // two's complement to convert to signed integer
         if (self.addr_rel & 0x80) != 0 {
             self.addr_rel |= 0xFF00;
         }
+    }
 
-        0
+    // The index is added to the low byte first. The address read on that cycle is wrong when the
+    // addition carries into the high byte, which then costs a cycle to fix. Instructions that write
+    // memory always spend that cycle, since they cannot let the wrong address through.
+    // The 65C02 reads the last operand byte again instead, and lets shifts skip the cycle.
+    fn index(&mut self, base: u16, index: u8) {
+        self.addr_abs = base.wrapping_add(index as u16);
+
+        let crossed = (self.addr_abs & 0xFF00) != (base & 0xFF00);
+        let forced = match self.variant {
+            Variant::Cmos65C02 => self.writes_memory() &&
+                !matches!(self.instruction().operate, Opcode::ASL | Opcode::LSR | Opcode::ROL | Opcode::ROR),
+            _ => self.writes_memory(),
+        };
+
+        if crossed || forced {
+            match self.variant {
+                Variant::Cmos65C02 => self.read(self.program_counter.wrapping_sub(1), false),
+                _ => self.read((base & 0xFF00) | (self.addr_abs & 0x00FF), false),
+            };
+        }
+    }
+
+    fn writes_memory(&self) -> bool {
+        matches!(
+            self.instruction().operate,
+            Opcode::STA | Opcode::STX | Opcode::STY | Opcode::SAX | Opcode::STZ |
+            Opcode::AHX | Opcode::SHX | Opcode::SHY | Opcode::TAS |
+            Opcode::ASL | Opcode::LSR | Opcode::ROL | Opcode::ROR | Opcode::INC | Opcode::DEC |
+            Opcode::SLO | Opcode::RLA | Opcode::SRE | Opcode::RRA | Opcode::DCP | Opcode::ISB |
+            Opcode::TRB | Opcode::TSB
+        )
     }
+
     // fetches data from memory using the address mode
     fn fetch(&mut self) -> u8 {
-        if references::INSTRUCTION_LOOKUP[self.opcode as usize].addrmode != AddressingMode::IMP {
+        let addrmode = &self.instruction().addrmode;
+        if !matches!(addrmode, AddressingMode::IMP | AddressingMode::ACC) {
             self.fetched = self.read(self.addr_abs, false);
         }
 
+        // Operands of immediate instructions are logged as code
+        match addrmode {
+            AddressingMode::IMP | AddressingMode::ACC | AddressingMode::IMM => {},
+            AddressingMode::IZX | AddressingMode::IZY | AddressingMode::IZP => self.bus.log_code_data(self.addr_abs, cdl::DATA | cdl::INDIRECT_DATA),
+            _ => self.bus.log_code_data(self.addr_abs, cdl::DATA),
+        }
+
         self.fetched
     }
 
+    // Read-modify-write instructions write the unmodified value back while they compute the result,
+    // the 65C02 reads it again instead
+    fn modify(&mut self, value: u8) {
+        if self.instruction().addrmode == AddressingMode::ACC {
+            self.accumulator = value;
+        } else {
+            if self.variant == Variant::Cmos65C02 {
+                self.read(self.addr_abs, false);
+            } else {
+                self.write(self.addr_abs, self.fetched);
+            }
+            self.write(self.addr_abs, value);
+        }
+    }
+
     // Instructions
-    fn and(&mut self) -> u8 {
+    fn and(&mut self) {
         self.fetch();
         self.accumulator &= self.fetched;
         self.set_flag(StatusFlag::Z, self.accumulator == 0x00);
         self.set_flag(StatusFlag::N, self.accumulator & 0x80 != 0);
-        1
     }
 
-
-    fn bcs(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::C) == 1 {
-            self.cycles += 1;
-            self.addr_abs = self.program_counter + self.addr_rel;
-
-            // If the branch crosses a page boundary, an additional cycle is required
-            if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
+    // A taken branch spends a cycle reading the next opcode while adding the offset to the low byte,
+    // and another one at the wrong address when the target is on a different page
+    fn branch(&mut self, condition: bool) {
+        if condition {
+            // Interrupts that first show up on the operand cycle are not polled until after the next instruction
+            if self.run_interrupt && !self.prev_run_interrupt {
+                self.run_interrupt = false;
             }
 
-            self.program_counter = self.addr_abs;
-        }
-
-        0
-    }
-
-    fn bcc(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::C) == 0{
-            self.cycles += 1;
-            self.addr_abs = self.program_counter + self.addr_rel;
+            self.read(self.program_counter, false);
+            self.addr_abs = self.program_counter.wrapping_add(self.addr_rel);
 
-            // If the branch crosses a page boundary, an additional cycle is required
             if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
+                self.read((self.program_counter & 0xFF00) | (self.addr_abs & 0x00FF), false);
             }
 
             self.program_counter = self.addr_abs;
         }
-
-        0
     }
 
-    fn beq(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::Z) == 1 {
-            self.cycles += 1;
-            self.addr_abs = self.program_counter + self.addr_rel;
-
-            // If the branch crosses a page boundary, an additional cycle is required
-            if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
-            }
-
-            self.program_counter = self.addr_abs;
-        }
-
-        0
+    fn bcs(&mut self) {
+        self.branch(self.get_flag(StatusFlag::C) == 1);
     }
 
-    fn bmi(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::N) == 1{
-            self.cycles += 1;
-            self.addr_abs = self.program_counter + self.addr_rel;
-
-            // If the branch crosses a page boundary, an additional cycle is required
-            if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
-            }
-
-            self.program_counter = self.addr_abs;
-        }
-
-        0
+    fn bcc(&mut self) {
+        self.branch(self.get_flag(StatusFlag::C) == 0);
     }
 
-    fn bne(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::Z) == 0 {
-            self.cycles += 1;
-            self.addr_abs = self.program_counter.wrapping_add(self.addr_rel);
-
-            // If the branch crosses a page boundary, an additional cycle is required
-            if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
-            }
-
-            self.program_counter = self.addr_abs;
-        }
-
-        0
+    fn beq(&mut self) {
+        self.branch(self.get_flag(StatusFlag::Z) == 1);
     }
 
-    fn bpl(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::N) == 0{
-            self.cycles += 1;
-            self.addr_abs = self.program_counter + self.addr_rel;
-
-            // If the branch crosses a page boundary, an additional cycle is required
-            if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
-            }
-
-            self.program_counter = self.addr_abs;
-        }
-
-        0
+    fn bmi(&mut self) {
+        self.branch(self.get_flag(StatusFlag::N) == 1);
     }
 
-    fn bvc(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::V) == 0{
-            self.cycles += 1;
-            self.addr_abs = self.program_counter + self.addr_rel;
-
-            // If the branch crosses a page boundary, an additional cycle is required
-            if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
-            }
-
-            self.program_counter = self.addr_abs;
-        }
-
-        0
+    fn bne(&mut self) {
+        self.branch(self.get_flag(StatusFlag::Z) == 0);
     }
 
-    fn bvs(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::V) == 1{
-            self.cycles += 1;
-            self.addr_abs = self.program_counter + self.addr_rel;
-
-            // If the branch crosses a page boundary, an additional cycle is required
-            if (self.addr_abs & 0xFF00) != (self.program_counter & 0xFF00) {
-                self.cycles += 1;
-            }
+    fn bpl(&mut self) {
+        self.branch(self.get_flag(StatusFlag::N) == 0);
+    }
 
-            self.program_counter = self.addr_abs;
-        }
+    fn bvc(&mut self) {
+        self.branch(self.get_flag(StatusFlag::V) == 0);
+    }
 
-        0
+    fn bvs(&mut self) {
+        self.branch(self.get_flag(StatusFlag::V) == 1);
     }
 
-    fn jmp(&mut self) -> u8 {
+    fn jmp(&mut self) {
         self.program_counter = self.addr_abs;
-        0
     }
 
-    fn jsr(&mut self) -> u8 {
-        self.program_counter -= 1;
+    fn jsr(&mut self) {
+        let lo = self.read(self.program_counter, false) as u16;
+        self.program_counter = self.program_counter.wrapping_add(1);
+
+        self.peek_stack();
 
-        self.write(0x0100 + self.stack_pointer as u16, (self.program_counter >> 8) as u8);
-        self.stack_pointer -= 1;
-        self.write(0x0100 + self.stack_pointer as u16, self.program_counter as u8);
-        self.stack_pointer -= 1;
+        // The pushed return address points at the last byte of the instruction
+        self.push((self.program_counter >> 8) as u8);
+        self.push(self.program_counter as u8);
 
+        let hi = self.read(self.program_counter, false) as u16;
+        self.addr_abs = (hi << 8) | lo;
         self.program_counter = self.addr_abs;
-        0
     }
 
-    fn lda(&mut self) -> u8 {
+    fn lda(&mut self) {
         self.fetch();
         self.accumulator = self.fetched;
 
         self.set_flag(StatusFlag::Z, self.accumulator == 0x00);
         self.set_flag(StatusFlag::N, self.accumulator & 0x80 != 0);
-
-        1
     }
 
-    fn ldx(&mut self) -> u8 {
+    fn ldx(&mut self) {
         self.fetch();
         self.x_register = self.fetched;
 
         self.set_flag(StatusFlag::Z, self.x_register == 0x00);
         self.set_flag(StatusFlag::N, self.x_register & 0x80 != 0);
-
-        1
     }
 
-    fn ldy(&mut self) -> u8 {
+    fn ldy(&mut self) {
         self.fetch();
         self.y_register = self.fetched;
 
         self.set_flag(StatusFlag::Z, self.y_register == 0x00);
         self.set_flag(StatusFlag::N, self.y_register & 0x80 != 0);
-
-        1
     }
 
-    fn clc(&mut self) -> u8 {
+    fn clc(&mut self) {
         self.set_flag(StatusFlag::C, false);
-        0
     }
 
-    fn cld(&mut self) -> u8 {
+    fn cld(&mut self) {
         self.set_flag(StatusFlag::D, false);
-        0
     }
 
-    fn cli(&mut self) -> u8 {
+    fn cli(&mut self) {
         self.set_flag(StatusFlag::I, false);
-        0
     }
 
-    fn clv(&mut self) -> u8 {
+    fn clv(&mut self) {
         self.set_flag(StatusFlag::V, false);
-        0
     }
 
-    fn sec(&mut self) -> u8 {
+    fn sec(&mut self) {
         self.set_flag(StatusFlag::C, true);
-        0
     }
 
-    fn sed(&mut self) -> u8 {
+    fn sed(&mut self) {
         self.set_flag(StatusFlag::D, true);
-        0
     }
 
-    fn sei(&mut self) -> u8 {
+    fn sei(&mut self) {
         self.set_flag(StatusFlag::I, true);
-        0
     }
 
-    fn sta(&mut self) -> u8 {
+    fn sta(&mut self) {
         self.write(self.addr_abs, self.accumulator);
-        0
     }
 
-    fn stx(&mut self) -> u8 {
+    fn stx(&mut self) {
         self.write(self.addr_abs, self.x_register);
-        0
     }
 
-    fn sty(&mut self) -> u8 {
+    fn sty(&mut self) {
         self.write(self.addr_abs, self.y_register);
-        0
     }
 
-    fn tax(&mut self) -> u8 {
+    fn tax(&mut self) {
         self.x_register = self.accumulator;
         self.set_flag(StatusFlag::Z, self.x_register == 0x00);
         self.set_flag(StatusFlag::N, (self.x_register & 0x80) != 0);
-        0
     }
 
-    fn tay(&mut self) -> u8 {
+    fn tay(&mut self) {
         self.y_register = self.accumulator;
         self.set_flag(StatusFlag::Z, self.y_register == 0x00);
         self.set_flag(StatusFlag::N, (self.y_register & 0x80) != 0);
-        0
     }
 
-    fn tsx(&mut self) -> u8 {
+    fn tsx(&mut self) {
         self.x_register = self.stack_pointer;
         self.set_flag(StatusFlag::Z, self.x_register == 0x00);
         self.set_flag(StatusFlag::N, (self.x_register & 0x80) != 0);
-        0
     }
 
-    fn txa(&mut self) -> u8 {
+    fn txa(&mut self) {
         self.accumulator = self.x_register;
         self.set_flag(StatusFlag::Z, self.accumulator == 0x00);
         self.set_flag(StatusFlag::N, (self.accumulator & 0x80) != 0);
-        0
     }
 
-    fn txs(&mut self) -> u8 {
+    fn txs(&mut self) {
         self.stack_pointer = self.x_register;
-        0
     }
 
-    fn tya(&mut self) -> u8 {
+    fn tya(&mut self) {
         self.accumulator = self.y_register;
         self.set_flag(StatusFlag::Z, self.accumulator == 0x00);
         self.set_flag(StatusFlag::N, (self.accumulator & 0x80) != 0);
-        0
     }
 
-    fn adc(&mut self) -> u8 {
+    fn adc(&mut self) {
+        self.fetch();
+        self.add(self.fetched);
+    }
+
+    fn sbc(&mut self) {
         self.fetch();
-        let temp: u16 = self.accumulator as u16 + self.fetched as u16 + self.get_flag(StatusFlag::C) as u16;
+        self.subtract(self.fetched);
+    }
+
+    fn decimal_mode(&self) -> bool {
+        self.variant.has_decimal_mode() && self.get_flag(StatusFlag::D) == 1
+    }
+
+    fn add(&mut self, value: u8) {
+        if self.decimal_mode() {
+            self.add_decimal(value);
+        } else {
+            self.add_with_carry(value);
+        }
+    }
+
+    fn subtract(&mut self, value: u8) {
+        if self.decimal_mode() {
+            self.subtract_decimal(value);
+        } else {
+            // Subtraction is addition of the one's complement of the operand
+            self.add_with_carry(value ^ 0xFF);
+        }
+    }
+
+    fn add_with_carry(&mut self, value: u8) {
+        let temp: u16 = self.accumulator as u16 + value as u16 + self.get_flag(StatusFlag::C) as u16;
         self.set_flag(StatusFlag::C, temp > 255);
         self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0);
         self.set_flag(StatusFlag::N, (temp & 0x80) != 0);
-        self.set_flag(StatusFlag::V, ((!(self.accumulator ^ self.fetched) & (self.accumulator ^ temp as u8)) & 0x80) != 0);
+        self.set_flag(StatusFlag::V, ((!(self.accumulator ^ value) & (self.accumulator ^ temp as u8)) & 0x80) != 0);
         self.accumulator = temp as u8;
-        1
     }
 
-    fn sbc(&mut self) -> u8 {
-        self.fetch();
-        let value: u16 = (self.fetched as u16) ^ 0x00FF;
-        let temp: u16 = self.accumulator as u16 + value + self.get_flag(StatusFlag::C) as u16;
-        self.set_flag(StatusFlag::C, (temp & 0xFF00) != 0);
-        self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0);
-        self.set_flag(StatusFlag::V, ((temp ^ self.accumulator as u16) & (temp ^ value) & 0x0080) != 0);
-        self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
+    // BCD addition, following the sequences in Bruce Clark's decimal mode tutorial on 6502.org.
+    // The NMOS chips take N and V from the sum before the high digit is adjusted and Z from the binary sum,
+    // the 65C02 spends an extra cycle to set N and Z from the result.
+    fn add_decimal(&mut self, value: u8) {
+        let a = self.accumulator as u16;
+        let b = value as u16;
+        let binary = a + b + self.get_flag(StatusFlag::C) as u16;
+
+        let mut lo = (a & 0x0F) + (b & 0x0F) + self.get_flag(StatusFlag::C) as u16;
+        if lo >= 0x0A {
+            lo = ((lo + 0x06) & 0x0F) + 0x10;
+        }
+
+        let mut temp = (a & 0xF0) + (b & 0xF0) + lo;
+        let signed = (a & 0xF0) as u8 as i8 as i16 + (b & 0xF0) as u8 as i8 as i16 + lo as i16;
+        self.set_flag(StatusFlag::V, !(-128..=127).contains(&signed));
+        self.set_flag(StatusFlag::N, (temp & 0x80) != 0);
+        self.set_flag(StatusFlag::Z, (binary & 0x00FF) == 0);
+
+        if temp >= 0xA0 {
+            temp += 0x60;
+        }
+        self.set_flag(StatusFlag::C, temp > 0xFF);
         self.accumulator = temp as u8;
-        1
+
+        if self.variant == Variant::Cmos65C02 {
+            self.read(self.addr_abs, false);
+            self.set_zn(self.accumulator);
+        }
     }
 
-    fn asl(&mut self) -> u8 {
+    // BCD subtraction, the flags are those of the binary subtraction except for N and Z on the 65C02
+    fn subtract_decimal(&mut self, value: u8) {
+        let a = self.accumulator as i16;
+        let b = value as i16;
+        let borrow = 1 - self.get_flag(StatusFlag::C) as i16;
+
+        let mut lo = (a & 0x0F) - (b & 0x0F) - borrow;
+        let temp = if self.variant == Variant::Cmos65C02 {
+            let mut temp = a - b - borrow;
+            if temp < 0 {
+                temp -= 0x60;
+            }
+            if lo < 0 {
+                temp -= 0x06;
+            }
+            temp
+        } else {
+            if lo < 0 {
+                lo = ((lo - 0x06) & 0x0F) - 0x10;
+            }
+            let mut temp = (a & 0xF0) - (b & 0xF0) + lo;
+            if temp < 0 {
+                temp -= 0x60;
+            }
+            temp
+        };
+
+        self.add_with_carry(value ^ 0xFF);
+        self.accumulator = temp as u8;
+
+        if self.variant == Variant::Cmos65C02 {
+            self.read(self.addr_abs, false);
+            self.set_zn(self.accumulator);
+        }
+    }
+
+    fn asl(&mut self) {
         self.fetch();
         let temp: u16 = (self.fetched as u16) << 1;
         self.set_flag(StatusFlag::C, (temp & 0xFF00) > 0);
         self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x00);
         self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        if references::INSTRUCTION_LOOKUP[self.opcode as usize].addrmode == AddressingMode::IMP {
-            self.accumulator = temp as u8;
-        } else {
-            self.write(self.addr_abs, temp as u8);
-        }
-        0
+        self.modify(temp as u8);
     }
 
-    fn lsr(&mut self) -> u8 {
+    fn lsr(&mut self) {
         self.fetch();
         let temp: u16 = (self.fetched as u16) >> 1;
-        self.set_flag(StatusFlag::C, (temp & 0x0001) != 0);
+        self.set_flag(StatusFlag::C, (self.fetched & 0x01) != 0);
         self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
         self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        if references::INSTRUCTION_LOOKUP[self.opcode as usize].addrmode == AddressingMode::IMP {
-            self.accumulator = temp as u8;
-        } else {
-            self.write(self.addr_abs, temp as u8);
-        }
-        0
+        self.modify(temp as u8);
     }
 
-    fn rol(&mut self) -> u8 {
+    fn rol(&mut self) {
         self.fetch();
         let temp: u16 = (self.fetched as u16) << 1 | self.get_flag(StatusFlag::C) as u16;
         self.set_flag(StatusFlag::C, (temp & 0xFF00) != 0);
         self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
         self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        if references::INSTRUCTION_LOOKUP[self.opcode as usize].addrmode == AddressingMode::IMP {
-            self.accumulator = temp as u8;
-        } else {
-            self.write(self.addr_abs, temp as u8);
-        }
-        0
+        self.modify(temp as u8);
     }
 
-    fn ror(&mut self) -> u8 {
+    fn ror(&mut self) {
         self.fetch();
         let temp: u16 = (self.get_flag(StatusFlag::C) as u16) << 7 | (self.fetched as u16) >> 1;
         self.set_flag(StatusFlag::C, (self.fetched & 0x01) != 0);
         self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
         self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        if references::INSTRUCTION_LOOKUP[self.opcode as usize].addrmode == AddressingMode::IMP {
-            self.accumulator = temp as u8;
-        } else {
-            self.write(self.addr_abs, temp as u8);
-        }
-        0
+        self.modify(temp as u8);
     }
 
-    fn cmp(&mut self) -> u8 {
+    fn cmp(&mut self) {
         self.fetch();
-        let temp: u16 = self.accumulator as u16 - self.fetched as u16;
-        self.set_flag(StatusFlag::C, self.accumulator >= self.fetched);
-        self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
-        self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        1
+        self.compare(self.accumulator, self.fetched);
     }
 
-    fn cpx(&mut self) -> u8 {
+    fn cpx(&mut self) {
         self.fetch();
-        let temp: u16 = self.x_register as u16 - self.fetched as u16;
-        self.set_flag(StatusFlag::C, self.x_register >= self.fetched);
-        self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
-        self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        0
+        self.compare(self.x_register, self.fetched);
     }
 
-    fn cpy(&mut self) -> u8 {
+    fn cpy(&mut self) {
         self.fetch();
-        let temp: u16 = self.y_register as u16 - self.fetched as u16;
-        self.set_flag(StatusFlag::C, self.y_register >= self.fetched);
-        self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
-        self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        0
+        self.compare(self.y_register, self.fetched);
     }
 
-    fn inc(&mut self) -> u8 {
+    fn inc(&mut self) {
         self.fetch();
-        let temp: u16 = self.fetched as u16 + 1;
-        self.write(self.addr_abs, temp as u8);
-        self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
-        self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        0
+        let temp = self.fetched.wrapping_add(1);
+        self.modify(temp);
+        self.set_zn(temp);
     }
 
-    fn inx(&mut self) -> u8 {
+    fn inx(&mut self) {
         self.x_register = self.x_register.wrapping_add(1);
         self.set_flag(StatusFlag::Z, self.x_register == 0x00);
         self.set_flag(StatusFlag::N, (self.x_register & 0x80) != 0);
-        0
     }
 
-    fn iny(&mut self) -> u8 {
+    fn iny(&mut self) {
         self.y_register = self.y_register.wrapping_add(1);
         self.set_flag(StatusFlag::Z, self.y_register == 0x00);
         self.set_flag(StatusFlag::N, (self.y_register & 0x80) != 0);
-        0
     }
 
-    fn dec(&mut self) -> u8 {
+    fn dec(&mut self) {
         self.fetch();
-        let temp: u16 = self.fetched as u16 - 1;
-        self.write(self.addr_abs, temp as u8);
-        self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
-        self.set_flag(StatusFlag::N, (temp & 0x0080) != 0);
-        0
+        let temp = self.fetched.wrapping_sub(1);
+        self.modify(temp);
+        self.set_zn(temp);
     }
 
-    fn dex(&mut self) -> u8 {
+    fn dex(&mut self) {
         self.x_register = self.x_register.wrapping_sub(1);
         self.set_flag(StatusFlag::Z, self.x_register == 0x00);
         self.set_flag(StatusFlag::N, (self.x_register & 0x80) != 0);
-        0
     }
 
-    fn dey(&mut self) -> u8 {
+    fn dey(&mut self) {
         self.y_register = self.y_register.wrapping_sub(1);
         self.set_flag(StatusFlag::Z, self.y_register == 0x00);
         self.set_flag(StatusFlag::N, (self.y_register & 0x80) != 0);
-        0
     }
 
-    fn eor(&mut self) -> u8 {
+    fn eor(&mut self) {
         self.fetch();
-        self.accumulator = self.accumulator ^ self.fetched;
+        self.accumulator ^= self.fetched;
         self.set_flag(StatusFlag::Z, self.accumulator == 0x00);
         self.set_flag(StatusFlag::N, (self.accumulator & 0x80) != 0);
-        1
     }
 
-    fn ora(&mut self) -> u8 {
+    fn ora(&mut self) {
         self.fetch();
-        self.accumulator = self.accumulator | self.fetched;
+        self.accumulator |= self.fetched;
         self.set_flag(StatusFlag::Z, self.accumulator == 0x00);
         self.set_flag(StatusFlag::N, (self.accumulator & 0x80) != 0);
-        1
     }
 
-    fn bit(&mut self) -> u8 {
+    fn bit(&mut self) {
         self.fetch();
         let temp: u16 = self.accumulator as u16 & self.fetched as u16;
         self.set_flag(StatusFlag::Z, (temp & 0x00FF) == 0x0000);
+
+        // The immediate form of the 65C02 only tests, it leaves N and V alone
+        if self.instruction().addrmode == AddressingMode::IMM {
+            return;
+        }
         self.set_flag(StatusFlag::N, (self.fetched & (1 << 7)) != 0);
         self.set_flag(StatusFlag::V, (self.fetched & (1 << 6)) != 0);
-        0
     }
 
-    fn pha(&mut self) -> u8 {
-        self.write(0x0100 + self.stack_pointer as u16, self.accumulator);
-        self.stack_pointer -= 1;
-        0
+    fn pha(&mut self) {
+        self.push(self.accumulator);
     }
 
-    fn php(&mut self) -> u8 {
-        self.write(0x0100 + self.stack_pointer as u16, self.status | StatusFlag::B as u8 | StatusFlag::U as u8);
+    fn php(&mut self) {
+        self.push(self.status | StatusFlag::B as u8 | StatusFlag::U as u8);
         self.set_flag(StatusFlag::B, false);
         self.set_flag(StatusFlag::U, false);
-        self.stack_pointer -= 1;
-        0
     }
 
-    fn pla(&mut self) -> u8 {
-        self.stack_pointer += 1;
-        self.accumulator = self.read(0x0100 + self.stack_pointer as u16, false);
+    fn pla(&mut self) {
+        self.peek_stack();
+        self.accumulator = self.pull();
         self.set_flag(StatusFlag::Z, self.accumulator == 0x00);
         self.set_flag(StatusFlag::N, (self.accumulator & 0x80) != 0);
-        0
     }
 
-    fn plp(&mut self) -> u8 {
-        self.stack_pointer += 1;
-        self.status = self.read(0x0100 + self.stack_pointer as u16, false);
+    fn plp(&mut self) {
+        self.peek_stack();
+        self.status = self.pull();
+        self.set_flag(StatusFlag::B, false);
         self.set_flag(StatusFlag::U, true);
-        0
     }
 
-    fn irq(&mut self) -> u8 {
-        if self.get_flag(StatusFlag::I) == 0 {
-            self.write(0x0100 + self.stack_pointer as u16, ((self.program_counter >> 8) & 0x00FF) as u8);
-            self.stack_pointer -= 1;
-            self.write(0x0100 + self.stack_pointer as u16, (self.program_counter & 0x00FF) as u8);
-            self.stack_pointer -= 1;
-
-            self.set_flag(StatusFlag::B, false);
-            self.set_flag(StatusFlag::U, true);
-            self.set_flag(StatusFlag::I, true);
-            self.write(0x0100 + self.stack_pointer as u16, self.status);
-            self.stack_pointer -= 1;
+    // Shared by IRQ and NMI, the two opcode cycles are spent reading the next instruction without executing it
+    fn interrupt(&mut self) {
+        self.read(self.program_counter, false);
+        self.read(self.program_counter, false);
 
-            self.addr_abs = 0xFFFE;
-            let lo = self.read(self.addr_abs, false) as u16;
-            let hi = self.read(self.addr_abs + 1, false) as u16;
-            self.program_counter = (hi << 8) | lo;
+        self.push((self.program_counter >> 8) as u8);
+        self.push(self.program_counter as u8);
 
-            self.cycles = 7;
-        }
-
-        0
-    }
-
-    fn nmi(&mut self) -> u8 {
-        self.write(0x0100 + self.stack_pointer as u16, ((self.program_counter >> 8) & 0x00FF) as u8);
-        self.stack_pointer -= 1;
-        self.write(0x0100 + self.stack_pointer as u16, (self.program_counter & 0x00FF) as u8);
-        self.stack_pointer -= 1;
+        let return_address = self.program_counter;
+        let vector = self.interrupt_vector();
+        self.interrupted = Some(vector);
 
         self.set_flag(StatusFlag::B, false);
         self.set_flag(StatusFlag::U, true);
+        self.push(self.status);
         self.set_flag(StatusFlag::I, true);
-        self.write(0x0100 + self.stack_pointer as u16, self.status);
-        self.stack_pointer -= 1;
+        if self.variant == Variant::Cmos65C02 {
+            self.set_flag(StatusFlag::D, false);
+        }
 
-        self.addr_abs = 0xFFFA;
+        self.addr_abs = vector;
         let lo = self.read(self.addr_abs, false) as u16;
         let hi = self.read(self.addr_abs + 1, false) as u16;
         self.program_counter = (hi << 8) | lo;
 
-        self.cycles = 8;
-        0
+        let kind = if vector == 0xFFFA { FrameKind::Nmi } else { FrameKind::Irq };
+        let frame = Frame { kind, source: return_address, target: self.program_counter, stack_pointer: self.stack_pointer };
+        self.call_stack.push(self.stack_pointer.wrapping_add(3), frame);
     }
 
-    fn rti(&mut self) -> u8 {
-        self.stack_pointer += 1;
-        self.status = self.read(0x0100 + self.stack_pointer as u16, false);
+    // The vector is picked after the return address is pushed. An NMI arriving before then takes over
+    // a BRK or IRQ that is already under way, which then never reaches its own handler.
+    fn interrupt_vector(&mut self) -> u16 {
+        if self.nmi_pending {
+            self.nmi_pending = false;
+            self.run_interrupt = false;
+            self.prev_run_interrupt = false;
+            0xFFFA
+        } else {
+            0xFFFE
+        }
+    }
+
+    fn rti(&mut self) {
+        self.peek_stack();
+
+        self.status = self.pull();
         self.status &= !(StatusFlag::B as u8);
         self.status &= !(StatusFlag::U as u8);
 
-        self.stack_pointer += 1;
-        let lo = self.read(0x0100 + self.stack_pointer as u16, false) as u16;
-        self.stack_pointer += 1;
-        let hi = self.read(0x0100 + self.stack_pointer as u16, false) as u16;
+        let lo = self.pull() as u16;
+        let hi = self.pull() as u16;
         self.program_counter = (hi << 8) | lo;
-
-        0
     }
 
-    fn rts(&mut self) -> u8 {
-        self.stack_pointer += 1;
-        let lo = self.read(0x0100 + self.stack_pointer as u16, false) as u16;
-        self.stack_pointer += 1;
-        let hi = self.read(0x0100 + self.stack_pointer as u16, false) as u16;
+    fn rts(&mut self) {
+        self.peek_stack();
+
+        let lo = self.pull() as u16;
+        let hi = self.pull() as u16;
         self.program_counter = (hi << 8) | lo;
 
-        self.program_counter += 1;
-        0
+        // The pulled address is the last byte of the JSR, it is read while moving past it
+        self.read(self.program_counter, false);
+        self.program_counter = self.program_counter.wrapping_add(1);
     }
 
-    fn brk(&mut self) -> u8 {
-        self.program_counter += 1;
+    fn brk(&mut self) {
+        // The byte after BRK is padding, it was read by the implied addressing mode
+        self.program_counter = self.program_counter.wrapping_add(1);
+
+        self.push((self.program_counter >> 8) as u8);
+        self.push(self.program_counter as u8);
 
+        let vector = self.interrupt_vector();
+
+        // The pushed copy has B set, the I flag is only set afterwards
+        self.push(self.status | StatusFlag::B as u8 | StatusFlag::U as u8);
         self.set_flag(StatusFlag::I, true);
-        self.write(0x0100 + self.stack_pointer as u16, ((self.program_counter >> 8) & 0x00FF) as u8);
-        self.stack_pointer -= 1;
-        self.write(0x0100 + self.stack_pointer as u16, (self.program_counter & 0x00FF) as u8);
-        self.stack_pointer -= 1;
-
-        self.set_flag(StatusFlag::B, true);
-        self.write(0x0100 + self.stack_pointer as u16, self.status);
-        self.stack_pointer -= 1;
-        self.set_flag(StatusFlag::B, false);
+        if self.variant == Variant::Cmos65C02 {
+            self.set_flag(StatusFlag::D, false);
+        }
 
-        self.addr_abs = 0xFFFE;
+        self.addr_abs = vector;
         let lo = self.read(self.addr_abs, false) as u16;
         let hi = self.read(self.addr_abs + 1, false) as u16;
         self.program_counter = (hi << 8) | lo;
+    }
+
+    fn nop(&mut self) {
+        // The unofficial NOPs with an operand still read it from memory
+        self.fetch();
+
+        // $5C on the 65C02 keeps the bus busy for four more cycles
+        if self.variant == Variant::Cmos65C02 && self.opcode == 0x5C {
+            for _ in 0..4 {
+                self.read(self.addr_abs, false);
+            }
+        }
+    }
+
+    // Unofficial Instructions
+    // Combined instructions reuse the same ALU logic as their official counterparts
+
+    fn set_zn(&mut self, value: u8) {
+        self.set_flag(StatusFlag::Z, value == 0x00);
+        self.set_flag(StatusFlag::N, (value & 0x80) != 0);
+    }
+
+    fn compare(&mut self, register: u8, value: u8) {
+        let temp = register.wrapping_sub(value);
+        self.set_flag(StatusFlag::C, register >= value);
+        self.set_zn(temp);
+    }
+
+    fn alr(&mut self) {
+        self.fetch();
+        let temp = self.accumulator & self.fetched;
+        self.set_flag(StatusFlag::C, (temp & 0x01) != 0);
+        self.accumulator = temp >> 1;
+        self.set_zn(self.accumulator);
+    }
+
+    fn anc(&mut self) {
+        self.fetch();
+        self.accumulator &= self.fetched;
+        self.set_zn(self.accumulator);
+        self.set_flag(StatusFlag::C, (self.accumulator & 0x80) != 0);
+    }
+
+    fn arr(&mut self) {
+        self.fetch();
+        let temp = self.accumulator & self.fetched;
+        self.accumulator = (self.get_flag(StatusFlag::C) << 7) | (temp >> 1);
+        self.set_zn(self.accumulator);
+        // Carry comes from bit 6 and overflow from bit 6 XOR bit 5 of the result
+        self.set_flag(StatusFlag::C, (self.accumulator & 0x40) != 0);
+        self.set_flag(StatusFlag::V, ((self.accumulator >> 6) ^ (self.accumulator >> 5)) & 0x01 != 0);
+    }
+
+    fn axs(&mut self) {
+        self.fetch();
+        let temp = self.accumulator & self.x_register;
+        self.set_flag(StatusFlag::C, temp >= self.fetched);
+        self.x_register = temp.wrapping_sub(self.fetched);
+        self.set_zn(self.x_register);
+    }
+
+    fn dcp(&mut self) {
+        self.fetch();
+        let temp = self.fetched.wrapping_sub(1);
+        self.modify(temp);
+        self.compare(self.accumulator, temp);
+    }
+
+    fn isb(&mut self) {
+        self.fetch();
+        let temp = self.fetched.wrapping_add(1);
+        self.modify(temp);
+        self.subtract(temp);
+    }
+
+    fn las(&mut self) {
+        self.fetch();
+        let temp = self.fetched & self.stack_pointer;
+        self.accumulator = temp;
+        self.x_register = temp;
+        self.stack_pointer = temp;
+        self.set_zn(temp);
+    }
+
+    fn lax(&mut self) {
+        self.fetch();
+        self.accumulator = self.fetched;
+        self.x_register = self.fetched;
+        self.set_zn(self.accumulator);
+    }
 
-        0
+    fn rla(&mut self) {
+        self.fetch();
+        let temp = (self.fetched << 1) | self.get_flag(StatusFlag::C);
+        self.set_flag(StatusFlag::C, (self.fetched & 0x80) != 0);
+        self.modify(temp);
+        self.accumulator &= temp;
+        self.set_zn(self.accumulator);
+    }
+
+    fn rra(&mut self) {
+        self.fetch();
+        let temp = (self.get_flag(StatusFlag::C) << 7) | (self.fetched >> 1);
+        self.set_flag(StatusFlag::C, (self.fetched & 0x01) != 0);
+        self.modify(temp);
+        self.add(temp);
+    }
+
+    fn sax(&mut self) {
+        self.write(self.addr_abs, self.accumulator & self.x_register);
+    }
+
+    fn slo(&mut self) {
+        self.fetch();
+        let temp = self.fetched << 1;
+        self.set_flag(StatusFlag::C, (self.fetched & 0x80) != 0);
+        self.modify(temp);
+        self.accumulator |= temp;
+        self.set_zn(self.accumulator);
     }
 
-    fn nop(&mut self) -> u8 {
-        // nop is a special case because it doesn't do anything
-        // it's just a placeholder for the CPU to do nothing
-        // so we don't need to do anything here
-        // but we do need to return the number of cycles
-        match self.opcode {
-            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => 1,
-            _ => 0,
+    fn sre(&mut self) {
+        self.fetch();
+        let temp = self.fetched >> 1;
+        self.set_flag(StatusFlag::C, (self.fetched & 0x01) != 0);
+        self.modify(temp);
+        self.accumulator ^= temp;
+        self.set_zn(self.accumulator);
+    }
+
+    // Unstable Unofficial Instructions
+    // These follow the behaviour commonly documented for the NES 2A03
+
+    // The unstable stores AND the value with the high byte of the base address plus one.
+    // If indexing crossed a page, that value also replaces the high byte of the target address.
+    fn unstable_store(&mut self, value: u8, index: u8) {
+        let base = self.addr_abs.wrapping_sub(index as u16);
+        let temp = value & ((base >> 8) as u8).wrapping_add(1);
+
+        if (base & 0xFF00) != (self.addr_abs & 0xFF00) {
+            self.addr_abs = ((temp as u16) << 8) | (self.addr_abs & 0x00FF);
         }
+
+        self.write(self.addr_abs, temp);
+    }
+
+    fn ahx(&mut self) {
+        self.unstable_store(self.accumulator & self.x_register, self.y_register);
+    }
+
+    fn lxa(&mut self) {
+        self.fetch();
+        self.accumulator = (self.accumulator | UNSTABLE_MAGIC) & self.fetched;
+        self.x_register = self.accumulator;
+        self.set_zn(self.accumulator);
+    }
+
+    fn shx(&mut self) {
+        self.unstable_store(self.x_register, self.y_register);
+    }
+
+    fn shy(&mut self) {
+        self.unstable_store(self.y_register, self.x_register);
+    }
+
+    fn tas(&mut self) {
+        self.stack_pointer = self.accumulator & self.x_register;
+        self.unstable_store(self.stack_pointer, self.y_register);
     }
 
-    fn xxx(&mut self) -> u8 {
-        0
+    fn xaa(&mut self) {
+        self.fetch();
+        self.accumulator = (self.accumulator | UNSTABLE_MAGIC) & self.x_register & self.fetched;
+        self.set_zn(self.accumulator);
+    }
+
+    // The NMOS lock-up opcodes halt the CPU
+    fn xxx(&mut self) {
+        self.jammed = Some(self.program_counter.wrapping_sub(1));
+    }
+
+    // 65C02 Instructions
+
+    fn bra(&mut self) {
+        self.branch(true);
     }
-    
+
+    fn phx(&mut self) {
+        self.push(self.x_register);
+    }
+
+    fn phy(&mut self) {
+        self.push(self.y_register);
+    }
+
+    fn plx(&mut self) {
+        self.peek_stack();
+        self.x_register = self.pull();
+        self.set_zn(self.x_register);
+    }
+
+    fn ply(&mut self) {
+        self.peek_stack();
+        self.y_register = self.pull();
+        self.set_zn(self.y_register);
+    }
+
+    fn stz(&mut self) {
+        self.write(self.addr_abs, 0x00);
+    }
+
+    // TRB and TSB set Z like BIT does, then clear or set the bits of A in memory
+    fn trb(&mut self) {
+        self.fetch();
+        self.set_flag(StatusFlag::Z, (self.accumulator & self.fetched) == 0x00);
+        self.modify(self.fetched & !self.accumulator);
+    }
+
+    fn tsb(&mut self) {
+        self.fetch();
+        self.set_flag(StatusFlag::Z, (self.accumulator & self.fetched) == 0x00);
+        self.modify(self.fetched | self.accumulator);
+    }
+
+    // Turning the power on clears the registers and puts the devices in their power-up state.
+    // SP starts at $00, the reset sequence then leaves it at $FD.
+    pub fn power_on(&mut self) {
+        self.bus.power_on();
+
+        self.accumulator = 0;
+        self.x_register = 0;
+        self.y_register = 0;
+        self.stack_pointer = 0x00;
+        self.status = StatusFlag::U as u8 | StatusFlag::I as u8;
+
+        self.nmi_pending = false;
+        self.run_interrupt = false;
+        self.prev_run_interrupt = false;
+        self.system_clock_counter = 0;
+        self.jammed = None;
+        self.interrupted = None;
+        self.call_stack.clear();
+
+        self.reset_sequence();
+    }
+
+    // Pressing the reset button leaves A, X, Y and RAM alone, it only sets I and moves SP down by three
     pub fn reset(&mut self) {
+        self.bus.reset();
+
+        self.nmi_pending = false;
+        self.jammed = None;
+        self.interrupted = None;
+        self.call_stack.clear();
+
+        self.reset_sequence();
+    }
+
+    // Runs the 7 cycle reset sequence, it goes through the motions of an interrupt with writes turned into reads
+    fn reset_sequence(&mut self) {
+        self.read(self.program_counter, false);
+        self.read(self.program_counter, false);
+        for _ in 0..3 {
+            self.read(0x0100 + self.stack_pointer as u16, false);
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        }
+
+        self.set_flag(StatusFlag::I, true);
+
         self.addr_abs = 0xFFFC;
         let lo = self.read(self.addr_abs, false) as u16;
         let hi = self.read(self.addr_abs + 1, false) as u16;
-        self.program_counter = 0x8000;
-        
-        self.accumulator = 0;
-        self.x_register = 0;
-        self.y_register = 0;
-        self.stack_pointer = 0xFD;
-        self.status = 0x00 | StatusFlag::U as u8;
+        self.program_counter = (hi << 8) | lo;
 
-        self.addr_rel = 0x0000;
-        self.addr_abs = 0x0000;
-        self.fetched = 0x00;
+        self.cycles = 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatusFlag, Variant};
+    use crate::opcodes::{references, Opcode};
+    use crate::assembler::testing::{cartridge, flat_memory, run, run_nes, run_until_brk, run_variant, PROGRAM_START};
+    use crate::klaus::InterruptTestMemory;
+    use crate::memory::Memory;
+    use crate::single_step::{Access, RecordingMemory};
+
+    fn flag<M: Memory>(cpu: &super::CPU<M>, flag: StatusFlag) -> bool {
+        cpu.get_flag(flag) == 1
+    }
+
+    #[test]
+    fn loads_and_stores() {
+        let cpu = run("
+            LDA #$42
+            STA $10
+            LDX $10
+            STX $0300
+            LDY $0300
+            STY $11
+            LDA #$80
+            LDX #$01
+            STA $0300,X
+            LDA #$00
+            LDA $02FF,X
+        ");
+
+        assert_eq!(cpu.peek(0x10), 0x42);
+        assert_eq!(cpu.peek(0x11), 0x42);
+        assert_eq!(cpu.peek(0x0301), 0x80);
+        assert_eq!(cpu.y_register, 0x42);
+        assert_eq!(cpu.accumulator, 0x42);
+    }
+
+    #[test]
+    fn indirect_addressing() {
+        let cpu = run("
+            LDA #$00
+            STA $20
+            LDA #$03
+            STA $21
+            LDA #$99
+            LDY #$05
+            STA ($20),Y
+            LDX #$04
+            LDA #$00
+            STA $24
+            LDA #$03
+            STA $25
+            LDA ($20,X)
+            TAY
+            LDA #$77
+            STA ($20,X)
+        ");
+
+        assert_eq!(cpu.peek(0x0305), 0x99);
+        assert_eq!(cpu.y_register, 0x00);
+        assert_eq!(cpu.peek(0x0300), 0x77);
+    }
+
+    #[test]
+    fn transfers_set_zero_and_negative() {
+        let cpu = run("
+            LDA #$80
+            TAX
+            TAY
+            LDA #$00
+            TXS
+            TSX
+            TXA
+        ");
+
+        assert_eq!(cpu.stack_pointer, 0x80);
+        assert_eq!(cpu.x_register, 0x80);
+        assert_eq!(cpu.y_register, 0x80);
+        assert_eq!(cpu.accumulator, 0x80);
+        assert!(flag(&cpu, StatusFlag::N));
+        assert!(!flag(&cpu, StatusFlag::Z));
+    }
+
+    #[test]
+    fn adc_sets_carry_and_overflow() {
+        let cpu = run("
+            CLC
+            LDA #$50
+            ADC #$50
+        ");
+
+        assert_eq!(cpu.accumulator, 0xA0);
+        assert!(flag(&cpu, StatusFlag::V));
+        assert!(flag(&cpu, StatusFlag::N));
+        assert!(!flag(&cpu, StatusFlag::C));
+
+        let cpu = run("
+            SEC
+            LDA #$FF
+            ADC #$00
+        ");
+
+        assert_eq!(cpu.accumulator, 0x00);
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::Z));
+        assert!(!flag(&cpu, StatusFlag::V));
+    }
+
+    #[test]
+    fn sbc_borrows() {
+        let cpu = run("
+            SEC
+            LDA #$10
+            SBC #$20
+        ");
+
+        assert_eq!(cpu.accumulator, 0xF0);
+        assert!(!flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::N));
+
+        let cpu = run("
+            SEC
+            LDA #$80
+            SBC #$01
+        ");
+
+        assert_eq!(cpu.accumulator, 0x7F);
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::V));
+    }
+
+    #[test]
+    fn logic() {
+        let cpu = run("
+            LDA #$F0
+            AND #$3C
+            ORA #$01
+            EOR #$FF
+        ");
+
+        assert_eq!(cpu.accumulator, !(0x30 | 0x01));
+    }
+
+    #[test]
+    fn bit_copies_bits_6_and_7() {
+        let cpu = run("
+            LDA #$C0
+            STA $10
+            LDA #$01
+            BIT $10
+        ");
+
+        assert!(flag(&cpu, StatusFlag::N));
+        assert!(flag(&cpu, StatusFlag::V));
+        assert!(flag(&cpu, StatusFlag::Z));
+    }
+
+    #[test]
+    fn shifts_and_rotates() {
+        let cpu = run("
+            LDA #$81
+            ASL A
+            STA $10
+            LDA #$81
+            LSR A
+            STA $11
+            SEC
+            LDA #$80
+            ROL A
+            STA $12
+            SEC
+            LDA #$01
+            ROR A
+            STA $13
+            LDA #$40
+            STA $14
+            ASL $14
+            LSR $14
+            ROL $14
+            ROR $14
+        ");
+
+        assert_eq!(cpu.peek(0x10), 0x02);
+        assert_eq!(cpu.peek(0x11), 0x40);
+        assert_eq!(cpu.peek(0x12), 0x01);
+        assert_eq!(cpu.peek(0x13), 0x80);
+        assert_eq!(cpu.peek(0x14), 0x40);
+        assert!(!flag(&cpu, StatusFlag::C));
+    }
+
+    #[test]
+    fn increments_and_decrements() {
+        let cpu = run("
+            LDX #$FF
+            INX
+            LDY #$00
+            DEY
+            LDA #$00
+            STA $10
+            DEC $10
+            DEC $10
+            INC $11
+        ");
+
+        assert_eq!(cpu.x_register, 0x00);
+        assert_eq!(cpu.y_register, 0xFF);
+        assert_eq!(cpu.peek(0x10), 0xFE);
+        assert_eq!(cpu.peek(0x11), 0x01);
+    }
+
+    #[test]
+    fn compares() {
+        let cpu = run("
+            LDA #$10
+            CMP #$20
+        ");
+        assert!(!flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::N));
+
+        let cpu = run("
+            LDX #$20
+            CPX #$20
+        ");
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::Z));
+
+        let cpu = run("
+            LDY #$30
+            CPY #$20
+        ");
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(!flag(&cpu, StatusFlag::Z));
+    }
+
+    #[test]
+    fn branches() {
+        let cpu = run("
+                LDX #$00
+                LDY #$05
+            loop:
+                INX
+                DEY
+                BNE loop
+                CLC
+                BCC skip
+                LDX #$FF
+            skip:
+                LDA #$80
+                BMI taken
+                LDX #$FF
+            taken:
+        ");
+
+        assert_eq!(cpu.x_register, 0x05);
+    }
+
+    #[test]
+    fn subroutines_and_stack() {
+        let cpu = run("
+                LDX #$FF
+                TXS
+                LDA #$12
+                PHA
+                JSR sub
+                PLA
+                JMP end
+            sub:
+                LDA #$34
+                PHA
+                PLA
+                STA $10
+                RTS
+            end:
+        ");
+
+        assert_eq!(cpu.stack_pointer, 0xFF);
+        assert_eq!(cpu.accumulator, 0x12);
+        assert_eq!(cpu.peek(0x10), 0x34);
+    }
+
+    #[test]
+    fn status_push_and_pull() {
+        let cpu = run("
+            SEC
+            SED
+            PHP
+            CLC
+            CLD
+            PLA
+            PHA
+            PLP
+        ");
+
+        // PHP pushes with the break and unused bits set
+        assert_eq!(cpu.accumulator & 0x30, 0x30);
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::D));
+        assert!(!flag(&cpu, StatusFlag::B));
+    }
+
+    #[test]
+    fn flag_instructions() {
+        let cpu = run("
+            SEC
+            SEI
+            LDA #$40
+            ADC #$40
+            CLV
+            CLI
+            CLC
+        ");
+
+        assert!(!flag(&cpu, StatusFlag::C));
+        assert!(!flag(&cpu, StatusFlag::I));
+        assert!(!flag(&cpu, StatusFlag::V));
+    }
+
+    #[test]
+    fn rti_restores_status_and_pc() {
+        let cpu = run("
+                LDA #$00
+                PHA
+                LDA #$00
+                PHA
+                LDA #$C3
+                PHA
+                RTI
+        ");
+
+        // Returns to $0000 which holds BRK
+        assert_eq!(cpu.program_counter, 0x0000);
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::N));
+    }
+
+    #[test]
+    fn unofficial_opcodes() {
+        let cpu = run("
+            LDA #$0F
+            STA $10
+            LAX $10
+            STX $12
+            LDA #$3C
+            LDX #$F0
+            SAX $11
+            DCP $10
+            ISB $11
+        ");
+
+        assert_eq!(cpu.peek(0x12), 0x0F);
+        assert_eq!(cpu.peek(0x10), 0x0E);
+        assert_eq!(cpu.peek(0x11), 0x31);
+    }
+
+    #[test]
+    fn runs_on_the_nes_bus() {
+        let cpu = run_nes("
+            LDA #$5A
+            STA $0805
+            LDX $0005
+        ");
+
+        // Internal RAM is mirrored every 2 KiB
+        assert_eq!(cpu.x_register, 0x5A);
+        assert_eq!(cpu.bus.cpu_vram[0x0005], 0x5A);
+    }
+
+    #[test]
+    fn flat_memory_is_writable_everywhere() {
+        let cpu = run("
+            LDA #$A5
+            STA $2002
+            STA $9000
+            LDX $2002
+        ");
+
+        assert_eq!(cpu.x_register, 0xA5);
+        assert_eq!(cpu.bus.mem_peek(0x9000), 0xA5);
+    }
+
+    // Runs one instruction placed at $0200 and returns every bus access it made
+    fn accesses(program: &[u8], x_register: u8) -> (u16, Vec<(u16, u8, Access)>) {
+        accesses_on(Variant::Ricoh2A03, program, x_register)
+    }
+
+    fn accesses_on(variant: Variant, program: &[u8], x_register: u8) -> (u16, Vec<(u16, u8, Access)>) {
+        let mut cpu = super::CPU::with_memory(RecordingMemory::new());
+        cpu.variant = variant;
+        cpu.bus.memory.load(0x0200, program);
+        cpu.program_counter = 0x0200;
+        cpu.x_register = x_register;
+
+        cpu.step();
+        (cpu.cycles, cpu.bus.accesses)
+    }
+
+    #[test]
+    fn page_crossing_reads_the_unfixed_address() {
+        // LDA $02FF,X
+        let (cycles, bus) = accesses(&[0xBD, 0xFF, 0x02], 0x01);
+
+        assert_eq!(cycles, 5);
+        assert_eq!(bus[3], (0x0200, 0xBD, Access::Read));
+        assert_eq!(bus[4], (0x0300, 0x00, Access::Read));
+
+        // LDA $0300,X
+        let (cycles, _) = accesses(&[0xBD, 0x00, 0x03], 0x01);
+        assert_eq!(cycles, 4);
+    }
+
+    #[test]
+    fn indexed_stores_always_take_the_extra_cycle() {
+        // STA $0300,X
+        let (cycles, bus) = accesses(&[0x9D, 0x00, 0x03], 0x01);
+
+        assert_eq!(cycles, 5);
+        assert_eq!(bus[3], (0x0301, 0x00, Access::Read));
+        assert_eq!(bus[4], (0x0301, 0x00, Access::Write));
+    }
+
+    #[test]
+    fn read_modify_write_writes_twice() {
+        // INC $0210
+        let (cycles, bus) = accesses(&[0xEE, 0x10, 0x02], 0x00);
+
+        assert_eq!(cycles, 6);
+        assert_eq!(bus[3..], [(0x0210, 0x00, Access::Read), (0x0210, 0x00, Access::Write), (0x0210, 0x01, Access::Write)]);
+    }
+
+    #[test]
+    fn taken_branches_read_ahead() {
+        // BNE -$03, not taken with Z set
+        let mut cpu = super::CPU::with_memory(RecordingMemory::new());
+        cpu.bus.memory.load(0x0200, &[0xD0, 0xFD]);
+        cpu.program_counter = 0x0200;
+        cpu.status = StatusFlag::Z as u8;
+        cpu.step();
+        assert_eq!(cpu.cycles, 2);
+
+        // BNE -$03, taken into the previous page
+        let (cycles, bus) = accesses(&[0xD0, 0xFD], 0x00);
+        assert_eq!(cycles, 4);
+        assert_eq!(bus[2], (0x0202, 0x00, Access::Read));
+        assert_eq!(bus[3], (0x02FF, 0x00, Access::Read));
+    }
+
+    #[test]
+    fn subroutine_calls_take_six_cycles() {
+        // JSR $0300, RTS
+        let mut cpu = super::CPU::with_memory(RecordingMemory::new());
+        cpu.bus.memory.load(0x0200, &[0x20, 0x00, 0x03]);
+        cpu.bus.memory.load(0x0300, &[0x60]);
+        cpu.program_counter = 0x0200;
+        cpu.stack_pointer = 0xFD;
+
+        cpu.step();
+        assert_eq!(cpu.cycles, 6);
+        assert_eq!(cpu.bus.accesses[2], (0x01FD, 0x00, Access::Read));
+        assert_eq!(cpu.bus.accesses[5], (0x0202, 0x03, Access::Read));
 
-        self.cycles = 8;
+        cpu.step();
+        assert_eq!(cpu.cycles, 6);
+        assert_eq!(cpu.program_counter, 0x0203);
     }
 
-    pub fn complete(&mut self) -> bool {
-        self.cycles == 0
+    #[test]
+    fn lookup_table_cycles_match_bus_accesses() {
+        // Without page crosses every instruction takes its base cycle count, branches are covered above
+        let instructions = references::INSTRUCTION_LOOKUP.iter()
+            .filter(|i| i.operate != Opcode::XXX && i.addrmode != super::AddressingMode::REL);
+
+        for instruction in instructions {
+            let (cycles, _) = accesses(&[instruction.hexcode, 0x00, 0x00], 0x00);
+            assert_eq!(cycles, instruction.cycles as u16, "{}", instruction);
+        }
+
+        let instructions = references::CMOS_INSTRUCTION_LOOKUP.iter()
+            .filter(|i| i.addrmode != super::AddressingMode::REL);
+
+        for instruction in instructions {
+            let (cycles, _) = accesses_on(Variant::Cmos65C02, &[instruction.hexcode, 0x00, 0x00], 0x00);
+            assert_eq!(cycles, instruction.cycles as u16, "65C02 {}", instruction);
+        }
     }
 
+    #[test]
+    fn jam_halts_until_reset() {
+        let mut cpu = super::CPU::with_memory(InterruptTestMemory::new(flat_memory("
+            LDX #$01
+            LDA #$01
+            STA $BFFC
+            CLI
+            .byte $02
+            INX
+        ")));
+        cpu.power_on();
+        for _ in 0..5 {
+            cpu.step();
+        }
+
+        assert_eq!(cpu.jammed, Some(0x8008));
+        assert_eq!(cpu.x_register, 0x01);
 
-}    
+        // Time goes on, but nothing is fetched and the IRQ is never taken
+        let cycles = cpu.total_cycles();
+        for _ in 0..10 {
+            cpu.step();
+        }
+        assert_eq!(cpu.total_cycles(), cycles + 10);
+        assert_eq!(cpu.jammed, Some(0x8008));
+        assert_eq!(cpu.x_register, 0x01);
+
+        cpu.reset();
+        assert_eq!(cpu.jammed, None);
+        assert_eq!(cpu.program_counter, PROGRAM_START);
+    }
+
+    #[test]
+    fn decimal_mode_depends_on_the_variant() {
+        let program = "
+            SED
+            CLC
+            LDA #$19
+            ADC #$28
+            STA $10
+            SEC
+            LDA #$50
+            SBC #$01
+        ";
+
+        let cpu = run_variant(Variant::Ricoh2A03, program);
+        assert_eq!(cpu.peek(0x10), 0x41);
+        assert_eq!(cpu.accumulator, 0x4F);
+
+        let cpu = run_variant(Variant::Nmos6502, program);
+        assert_eq!(cpu.peek(0x10), 0x47);
+        assert_eq!(cpu.accumulator, 0x49);
+    }
+
+    #[test]
+    fn decimal_flags() {
+        let program = "
+            SED
+            CLC
+            LDA #$99
+            ADC #$01
+        ";
+
+        // NMOS takes Z from the binary sum $9A
+        let cpu = run_variant(Variant::Nmos6502, program);
+        assert_eq!(cpu.accumulator, 0x00);
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(!flag(&cpu, StatusFlag::Z));
+
+        let cpu = run_variant(Variant::Cmos65C02, program);
+        assert_eq!(cpu.accumulator, 0x00);
+        assert!(flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::Z));
+
+        let cpu = run_variant(Variant::Cmos65C02, "
+            SED
+            SEC
+            LDA #$00
+            SBC #$01
+        ");
+        assert_eq!(cpu.accumulator, 0x99);
+        assert!(!flag(&cpu, StatusFlag::C));
+        assert!(flag(&cpu, StatusFlag::N));
+    }
+
+    #[test]
+    fn cmos_instructions() {
+        let cpu = run_variant(Variant::Cmos65C02, "
+                LDA #$FF
+                STA $10
+                STZ $10
+                LDX #$12
+                PHX
+                PLY
+                LDA #$0F
+                STA $11
+                LDA #$3C
+                TSB $11
+                LDA #$03
+                TRB $11
+                LDA #$00
+                STA $20
+                LDA #$03
+                STA $21
+                LDA #$77
+                STA ($20)
+                INC A
+                BRA skip
+                LDY #$FF
+            skip:
+                LDX #$01
+                JMP (table,X)
+                LDY #$FF
+            table:
+                .byte $00
+                .word end
+            end:
+        ");
+
+        assert_eq!(cpu.peek(0x10), 0x00);
+        assert_eq!(cpu.y_register, 0x12);
+        assert_eq!(cpu.peek(0x11), 0x3C);
+        assert!(!flag(&cpu, StatusFlag::Z));
+        assert_eq!(cpu.peek(0x0300), 0x77);
+        assert_eq!(cpu.accumulator, 0x78);
+    }
+
+    #[test]
+    fn cmos_fixes_the_indirect_jump() {
+        let program = "
+            LDA #$00
+            STA $02FF
+            LDA #$90
+            STA $0200
+            LDA #$A0
+            STA $0300
+            JMP ($02FF)
+        ";
+
+        assert_eq!(run_variant(Variant::Nmos6502, program).program_counter, 0x9000);
+        assert_eq!(run_variant(Variant::Cmos65C02, program).program_counter, 0xA000);
+    }
+
+    #[test]
+    fn cmos_read_modify_write_reads_twice() {
+        // INC $10
+        let (_, nmos) = accesses_on(Variant::Nmos6502, &[0xE6, 0x10], 0x00);
+        let (_, cmos) = accesses_on(Variant::Cmos65C02, &[0xE6, 0x10], 0x00);
+
+        assert_eq!(nmos[3], (0x0010, 0x00, Access::Write));
+        assert_eq!(cmos[3], (0x0010, 0x00, Access::Read));
+        assert_eq!(cmos[4], (0x0010, 0x01, Access::Write));
+    }
+
+    // Runs a program that raises IRQ by writing 1 to $BFFC, both vectors lead to a BRK at $FFF0
+    fn run_with_irq(source: &str) -> super::CPU<InterruptTestMemory> {
+        let mut cpu = super::CPU::with_memory(InterruptTestMemory::new(flat_memory(source)));
+        cpu.power_on();
+        run_until_brk(cpu)
+    }
+
+    #[test]
+    fn irq_is_taken_after_the_next_instruction() {
+        let cpu = run_with_irq("
+            CLI
+            LDA #$01
+            STA $BFFC
+            INX
+            INX
+        ");
+
+        assert_eq!(cpu.program_counter, 0xFFF0);
+        assert_eq!(cpu.x_register, 0x01);
+        assert!(flag(&cpu, StatusFlag::I));
+        // Return address and status pushed with B clear
+        assert_eq!(cpu.peek(0x01FD), 0x80);
+        assert_eq!(cpu.peek(0x01FC), 0x07);
+        assert_eq!(cpu.peek(0x01FB) & StatusFlag::B as u8, 0x00);
+    }
+
+    #[test]
+    fn cli_takes_effect_after_the_next_instruction() {
+        let cpu = run_with_irq("
+            LDA #$01
+            STA $BFFC
+            INX
+            CLI
+            INX
+            INX
+        ");
+
+        assert_eq!(cpu.program_counter, 0xFFF0);
+        assert_eq!(cpu.x_register, 0x02);
+    }
+
+    #[test]
+    fn sei_still_lets_a_pending_irq_through() {
+        let cpu = run_with_irq("
+            CLI
+            LDX #$00
+            LDA #$01
+            STA $BFFC
+            SEI
+            INX
+        ");
+
+        assert_eq!(cpu.program_counter, 0xFFF0);
+        assert_eq!(cpu.x_register, 0x00);
+        // The status pushed by the IRQ already has I set
+        assert_ne!(cpu.peek(0x01FB) & StatusFlag::I as u8, 0x00);
+    }
+
+    #[test]
+    fn masked_irq_is_ignored() {
+        let cpu = run_with_irq("
+            LDA #$01
+            STA $BFFC
+            INX
+        ");
+
+        assert_ne!(cpu.program_counter, 0xFFF0);
+        assert_eq!(cpu.x_register, 0x01);
+    }
+
+    // Raises NMI when the given bus access is about to happen
+    struct NmiAt {
+        memory: RecordingMemory,
+        access: usize,
+    }
+
+    impl Memory for NmiAt {
+        fn mem_read(&mut self, addr: u16) -> u8 {
+            self.memory.mem_read(addr)
+        }
+
+        fn mem_write(&mut self, addr: u16, data: u8) {
+            self.memory.mem_write(addr, data)
+        }
+
+        fn mem_peek(&self, addr: u16) -> u8 {
+            self.memory.mem_peek(addr)
+        }
+
+        fn poll_nmi(&mut self) -> bool {
+            self.memory.accesses.len() + 1 == self.access
+        }
+    }
+
+    fn brk_with_nmi_at(access: usize) -> super::CPU<NmiAt> {
+        let mut memory = RecordingMemory::new();
+        memory.memory.load(0x0200, &[0x00, 0x00]);
+        memory.memory.load(0xFFFA, &[0x00, 0x03, 0x00, 0x00, 0x00, 0x04]);
+
+        let mut cpu = super::CPU::with_memory(NmiAt { memory, access });
+        cpu.program_counter = 0x0200;
+        cpu.stack_pointer = 0xFD;
+        cpu.step();
+        cpu
+    }
+
+    #[test]
+    fn nmi_hijacks_brk() {
+        // Arriving while the return address is pushed, NMI takes over the vector fetch
+        let cpu = brk_with_nmi_at(4);
+        assert_eq!(cpu.program_counter, 0x0300);
+        assert_ne!(cpu.peek(0x01FB) & StatusFlag::B as u8, 0x00);
+        assert!(!cpu.nmi_pending);
+
+        // Any later, BRK reaches its handler and the NMI follows right after
+        let cpu = brk_with_nmi_at(5);
+        assert_eq!(cpu.program_counter, 0x0300);
+        assert_eq!(cpu.peek(0x01FA), 0x04);
+        assert_eq!(cpu.peek(0x01F9), 0x00);
+        assert_eq!(cpu.cycles, 7);
+    }
+
+    #[test]
+    fn power_on_starts_at_the_reset_vector() {
+        let cpu = super::CPU::new(cartridge("NOP"));
+
+        assert_eq!(cpu.program_counter, PROGRAM_START);
+        assert_eq!(cpu.stack_pointer, 0xFD);
+        assert_eq!(cpu.status, 0x24);
+        assert_eq!(cpu.total_cycles(), 7);
+    }
+
+    #[test]
+    fn soft_reset_keeps_registers() {
+        let mut cpu = run_nes("
+            LDA #$42
+            LDX #$17
+            LDY #$99
+            CLI
+            SEC
+            STA $0300
+        ");
+
+        cpu.reset();
+
+        assert_eq!(cpu.program_counter, PROGRAM_START);
+        assert_eq!(cpu.accumulator, 0x42);
+        assert_eq!(cpu.x_register, 0x17);
+        assert_eq!(cpu.y_register, 0x99);
+        assert_eq!(cpu.stack_pointer, 0xFA);
+        assert!(flag(&cpu, StatusFlag::I));
+        assert!(flag(&cpu, StatusFlag::C));
+        assert_eq!(cpu.peek(0x0300), 0x42);
+    }
+}
@@ -0,0 +1,628 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+
+use crate::cpu::CPU;
+use crate::expression::Expression;
+use crate::opcodes::Opcode;
+use crate::profiler::Profiler;
+use crate::storage;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write,
+    PpuRead,
+    PpuWrite,
+}
+
+// Bus accesses made since the debugger last looked. Nothing is recorded unless a breakpoint needs it.
+#[derive(Default)]
+pub struct AccessLog {
+    pub enabled: bool,
+    entries: Vec<(AccessKind, u16, u8)>,
+}
+
+impl AccessLog {
+    pub fn record(&mut self, kind: AccessKind, addr: u16, data: u8) {
+        if self.enabled {
+            self.entries.push((kind, addr, data));
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<(AccessKind, u16, u8)> {
+        std::mem::take(&mut self.entries)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BreakpointKind {
+    Execute,
+    Read,
+    Write,
+    PpuRead, // Watchpoints on the PPU address space, $0000-$3FFF
+    PpuWrite,
+}
+
+pub const BREAKPOINT_KINDS: [BreakpointKind; 5] = [
+    BreakpointKind::Execute, BreakpointKind::Read, BreakpointKind::Write, BreakpointKind::PpuRead, BreakpointKind::PpuWrite,
+];
+
+impl BreakpointKind {
+    fn matches(&self, access: AccessKind) -> bool {
+        matches!(
+            (self, access),
+            (BreakpointKind::Read, AccessKind::Read) | (BreakpointKind::Write, AccessKind::Write) |
+            (BreakpointKind::PpuRead, AccessKind::PpuRead) | (BreakpointKind::PpuWrite, AccessKind::PpuWrite)
+        )
+    }
+
+    // Name used in the saved breakpoints
+    fn key(&self) -> &'static str {
+        match self {
+            BreakpointKind::Execute => "execute",
+            BreakpointKind::Read => "read",
+            BreakpointKind::Write => "write",
+            BreakpointKind::PpuRead => "ppu-read",
+            BreakpointKind::PpuWrite => "ppu-write",
+        }
+    }
+}
+
+impl FromStr for BreakpointKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BREAKPOINT_KINDS.iter().copied().find(|kind| kind.key() == s).ok_or(format!("unknown breakpoint kind '{}'", s))
+    }
+}
+
+impl fmt::Display for BreakpointKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakpointKind::Execute => write!(f, "Execute"),
+            BreakpointKind::Read => write!(f, "Read"),
+            BreakpointKind::Write => write!(f, "Write"),
+            BreakpointKind::PpuRead => write!(f, "PPU read"),
+            BreakpointKind::PpuWrite => write!(f, "PPU write"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    pub kind: BreakpointKind,
+    pub start: u16,
+    pub end: u16, // Inclusive, equal to start for a single address
+    pub condition: Option<Expression>,
+    pub hit_count: Option<u32>, // Only pause from this hit onwards
+    pub hits: u32, // Times the address matched with the condition true
+    pub enabled: bool,
+}
+
+impl Breakpoint {
+    pub fn new(kind: BreakpointKind, start: u16, end: u16) -> Self {
+        Breakpoint {
+            kind,
+            start: start.min(end),
+            end: start.max(end),
+            condition: None,
+            hit_count: None,
+            hits: 0,
+            enabled: true,
+        }
+    }
+
+    pub fn contains(&self, addr: u16) -> bool {
+        (self.start..=self.end).contains(&addr)
+    }
+
+    // Address or range in the same syntax parse_range accepts
+    pub fn range(&self) -> String {
+        if self.start == self.end {
+            format!("${:04X}", self.start)
+        } else {
+            format!("${:04X}-${:04X}", self.start, self.end)
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "kind": self.kind.key(),
+            "start": self.start,
+            "end": self.end,
+            "condition": self.condition.as_ref().map(|condition| condition.source()),
+            "hit_count": self.hit_count,
+            "enabled": self.enabled,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Breakpoint, String> {
+        let kind = value["kind"].as_str().ok_or("breakpoint without a kind")?.parse()?;
+        let address = |field: &str| value[field].as_u64().and_then(|addr| u16::try_from(addr).ok())
+            .ok_or(format!("breakpoint without a valid {}", field));
+
+        let mut breakpoint = Breakpoint::new(kind, address("start")?, address("end")?);
+        breakpoint.condition = value["condition"].as_str().map(Expression::parse).transpose()?;
+        breakpoint.hit_count = value["hit_count"].as_u64().map(|count| count as u32);
+        breakpoint.enabled = value["enabled"].as_bool().unwrap_or(true);
+
+        Ok(breakpoint)
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.range())?;
+        if let Some(condition) = &self.condition {
+            write!(f, " if {}", condition)?;
+        }
+        if let Some(count) = self.hit_count {
+            write!(f, " from hit {}", count)?;
+        }
+        Ok(())
+    }
+}
+
+// A single address or an inclusive range, in hex with an optional $: "$8000", "2000-23FF"
+pub fn parse_range(text: &str) -> Result<(u16, u16), String> {
+    let address = |text: &str| {
+        let text = text.trim();
+        u16::from_str_radix(text.strip_prefix('$').unwrap_or(text), 16).map_err(|_| format!("invalid address '{}'", text))
+    };
+
+    match text.split_once('-') {
+        Some((start, end)) => Ok((address(start)?, address(end)?)),
+        None => address(text).map(|addr| (addr, addr)),
+    }
+}
+
+// What made the emulation pause
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    pub index: usize, // Of the breakpoint in Debugger::breakpoints
+    pub kind: BreakpointKind,
+    pub addr: u16,
+    pub data: Option<u8>, // Value read or written, none for execute breakpoints
+    pub pc: u16, // Instruction that caused the hit
+}
+
+impl fmt::Display for Hit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let data = self.data.unwrap_or(0);
+        match self.kind {
+            BreakpointKind::Execute => write!(f, "Execute breakpoint at ${:04X}", self.addr),
+            BreakpointKind::Read => write!(f, "Read ${:02X} from ${:04X} at PC ${:04X}", data, self.addr, self.pc),
+            BreakpointKind::Write => write!(f, "Write ${:02X} to ${:04X} at PC ${:04X}", data, self.addr, self.pc),
+            BreakpointKind::PpuRead => write!(f, "PPU read ${:02X} from ${:04X} at PC ${:04X}", data, self.addr, self.pc),
+            BreakpointKind::PpuWrite => write!(f, "PPU write ${:02X} to ${:04X} at PC ${:04X}", data, self.addr, self.pc),
+        }
+    }
+}
+
+// What the debugger runs the CPU for, until done or a breakpoint pauses it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Run, // Until paused
+    StepOver, // One instruction, a JSR runs until the subroutine returns
+    StepOut, // Until the RTS or RTI leaving the current subroutine or interrupt handler
+    RunTo(u16), // Until the program counter reaches the address
+    StepScanline, // Until the PPU starts the next scanline
+    StepFrame, // Until the PPU starts the next frame
+}
+
+// Command in progress, checked after every instruction
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Goal {
+    Run,
+    Instruction,
+    Return(u8), // Stack pointer back at or above this level
+    StepOut(u8), // RTS or RTI executed at or above this stack level
+    Address(u16),
+    Scanline(u16),
+    Frame(u64),
+}
+
+impl Goal {
+    // Interrupts taken at the end of a step over or step out are stepped over as well,
+    // instead of stopping at the first instruction of the handler
+    fn skips_interrupts(&self) -> bool {
+        matches!(self, Goal::Instruction | Goal::Return(_) | Goal::StepOut(_))
+    }
+}
+
+// Stack level at or above another one, the stack grows down from $01FF
+fn at_or_above(stack_pointer: u8, level: u8) -> bool {
+    stack_pointer.wrapping_sub(level) as i8 >= 0
+}
+
+// Runs the CPU an instruction at a time and stops it on breakpoints.
+// Execute breakpoints stop before the instruction runs, memory breakpoints right after the one that made the access.
+#[derive(Default)]
+pub struct Debugger {
+    pub breakpoints: Vec<Breakpoint>,
+    pub hit: Option<Hit>, // Cause of the last pause, cleared by the next step
+    pub path: Option<PathBuf>, // Where the breakpoints are saved
+    resume_at: Option<u16>, // Execute breakpoints at this address are skipped once, to resume after a hit
+    goal: Option<Goal>,
+    pub profiler: Profiler, // Sees every instruction the debugger runs while enabled
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Breakpoints saved at path, none if nothing was saved yet
+    pub fn load(path: PathBuf) -> Result<Debugger, String> {
+        let mut debugger = Debugger { path: Some(path.clone()), ..Debugger::default() };
+
+        let Some(value) = storage::read_json(&path)? else { return Ok(debugger) };
+        if let Some(breakpoints) = value["breakpoints"].as_array() {
+            debugger.breakpoints = breakpoints.iter().map(Breakpoint::from_json).collect::<Result<_, _>>()
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+
+        Ok(debugger)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+
+        let value = json!({ "breakpoints": self.breakpoints.iter().map(Breakpoint::to_json).collect::<Vec<_>>() });
+        storage::write_json(path, &value)
+    }
+
+    // Runs one instruction, unless an execute breakpoint stops it first
+    pub fn step(&mut self, cpu: &mut CPU) -> Option<Hit> {
+        let pc = cpu.program_counter;
+        self.hit = None;
+
+        if self.resume_at.take() != Some(pc) {
+            if let Some(hit) = self.check(cpu, pc, |kind| kind == BreakpointKind::Execute, pc, None) {
+                self.resume_at = Some(pc);
+                self.hit = Some(hit);
+                return self.hit.clone();
+            }
+        }
+
+        let enabled = |kinds: &[BreakpointKind]| self.breakpoints.iter().any(|b| b.enabled && kinds.contains(&b.kind));
+        cpu.bus.access_log.enabled = enabled(&[BreakpointKind::Read, BreakpointKind::Write]);
+        cpu.bus.ppu.access_log.enabled = enabled(&[BreakpointKind::PpuRead, BreakpointKind::PpuWrite]);
+
+        let start = cpu.total_cycles();
+        cpu.step();
+
+        if self.profiler.enabled {
+            self.profiler.record(cpu, pc, cpu.total_cycles() - start);
+        }
+
+        let mut accesses = cpu.bus.access_log.take();
+        accesses.extend(cpu.bus.ppu.access_log.take());
+
+        for (access, addr, data) in accesses {
+            let hit = self.check(cpu, pc, |kind| kind.matches(access), addr, Some(data));
+            if self.hit.is_none() {
+                self.hit = hit;
+            }
+        }
+
+        self.hit.clone()
+    }
+
+    // Starts a command, run advances it
+    pub fn start(&mut self, cpu: &CPU, command: Command) {
+        let instruction = &cpu.variant.instructions()[cpu.peek(cpu.program_counter) as usize];
+
+        self.goal = Some(match command {
+            Command::Run => Goal::Run,
+            Command::StepOver if instruction.operate == Opcode::JSR => Goal::Return(cpu.stack_pointer),
+            Command::StepOver => Goal::Instruction,
+            Command::StepOut => Goal::StepOut(cpu.stack_pointer),
+            Command::RunTo(addr) => Goal::Address(addr),
+            Command::StepScanline => Goal::Scanline(cpu.bus.ppu.scanline),
+            Command::StepFrame => Goal::Frame(cpu.bus.ppu.frame),
+        });
+    }
+
+    // Stops the command in progress
+    pub fn pause(&mut self) {
+        self.goal = None;
+    }
+
+    pub fn running(&self) -> bool {
+        self.goal.is_some()
+    }
+
+    // Advances the command in progress by at most the given number of CPU cycles, so a command that takes long
+    // (or never finishes, like stepping over a subroutine that never returns) can be spread over frames and paused
+    pub fn run(&mut self, cpu: &mut CPU, cycles: u64) -> Option<Hit> {
+        let end = cpu.total_cycles() + cycles;
+
+        while let Some(goal) = self.goal {
+            if cpu.total_cycles() >= end {
+                return None;
+            }
+
+            let instruction = &cpu.variant.instructions()[cpu.peek(cpu.program_counter) as usize];
+            let returning = matches!(instruction.operate, Opcode::RTS | Opcode::RTI);
+            let stack_pointer = cpu.stack_pointer;
+
+            if let Some(hit) = self.step(cpu) {
+                self.goal = None;
+                return Some(hit);
+            }
+
+            let done = match goal {
+                Goal::Run => false,
+                Goal::Instruction => true,
+                Goal::Return(level) => at_or_above(cpu.stack_pointer, level),
+                Goal::StepOut(level) => returning && at_or_above(stack_pointer, level),
+                Goal::Address(addr) => cpu.program_counter == addr,
+                Goal::Scanline(scanline) => cpu.bus.ppu.scanline != scanline,
+                Goal::Frame(frame) => cpu.bus.ppu.frame != frame,
+            };
+
+            if done {
+                // The interrupt pushed three bytes, it has returned once they are pulled again
+                self.goal = match cpu.interrupted {
+                    Some(_) if goal.skips_interrupts() => Some(Goal::Return(cpu.stack_pointer.wrapping_add(3))),
+                    _ => None,
+                };
+            }
+        }
+
+        None
+    }
+
+    // Counts a hit on every matching breakpoint whose condition holds, and returns the first one that pauses
+    fn check<F: Fn(BreakpointKind) -> bool>(&mut self, cpu: &CPU, pc: u16, kind: F, addr: u16, data: Option<u8>) -> Option<Hit> {
+        let mut hit = None;
+
+        for (index, breakpoint) in self.breakpoints.iter_mut().enumerate() {
+            if !breakpoint.enabled || !kind(breakpoint.kind) || !breakpoint.contains(addr) {
+                continue;
+            }
+
+            let access = data.map(|data| (addr, data));
+            if breakpoint.condition.as_ref().is_some_and(|condition| condition.eval(cpu, access) == 0) {
+                continue;
+            }
+
+            breakpoint.hits += 1;
+            if hit.is_none() && breakpoint.hit_count.is_none_or(|count| breakpoint.hits >= count) {
+                hit = Some(Hit { index, kind: breakpoint.kind, addr, data, pc });
+            }
+        }
+
+        hit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::cartridge;
+
+    const PROGRAM: &str = "
+            LDX #$00
+        loop:
+            INX
+            STX $0300
+            LDA $0300
+            CPX #$05
+            BNE loop
+            LDA #$00
+            STA $2006
+            LDA #$10
+            STA $2006
+            LDA $2007
+        ";
+
+    // Steps until a breakpoint pauses the CPU
+    fn run_to_hit(debugger: &mut Debugger, cpu: &mut CPU) -> Hit {
+        for _ in 0..100 {
+            if let Some(hit) = debugger.step(cpu) {
+                return hit;
+            }
+        }
+        panic!("no breakpoint hit");
+    }
+
+    #[test]
+    fn execute_breakpoint_stops_before_the_instruction_and_resumes() {
+        let mut cpu = CPU::new(cartridge(PROGRAM));
+        let mut debugger = Debugger::new();
+        debugger.breakpoints.push(Breakpoint::new(BreakpointKind::Execute, 0x8002, 0x8002));
+
+        let hit = run_to_hit(&mut debugger, &mut cpu);
+        assert_eq!(hit.to_string(), "Execute breakpoint at $8002");
+        assert_eq!(cpu.program_counter, 0x8002);
+        assert_eq!(cpu.x_register, 0x00);
+
+        // The next step runs INX instead of hitting the same breakpoint again
+        assert_eq!(debugger.step(&mut cpu), None);
+        assert_eq!(cpu.x_register, 0x01);
+
+        run_to_hit(&mut debugger, &mut cpu);
+        assert_eq!(cpu.x_register, 0x01);
+        assert_eq!(debugger.breakpoints[0].hits, 2);
+    }
+
+    #[test]
+    fn conditions_and_hit_counts() {
+        let mut cpu = CPU::new(cartridge(PROGRAM));
+        let mut debugger = Debugger::new();
+
+        let mut breakpoint = Breakpoint::new(BreakpointKind::Execute, 0x8002, 0x8002);
+        breakpoint.condition = Some(Expression::parse("X == #$03").unwrap());
+        debugger.breakpoints.push(breakpoint);
+
+        run_to_hit(&mut debugger, &mut cpu);
+        assert_eq!(cpu.x_register, 0x03);
+
+        let mut breakpoint = Breakpoint::new(BreakpointKind::Read, 0x0300, 0x0300);
+        breakpoint.hit_count = Some(2);
+        debugger.breakpoints = vec![breakpoint];
+
+        let hit = run_to_hit(&mut debugger, &mut cpu);
+        assert_eq!(hit.to_string(), "Read $05 from $0300 at PC $8006");
+        assert_eq!(debugger.breakpoints[0].hits, 2);
+    }
+
+    #[test]
+    fn write_breakpoint_on_a_range() {
+        let mut cpu = CPU::new(cartridge(PROGRAM));
+        let mut debugger = Debugger::new();
+
+        let mut breakpoint = Breakpoint::new(BreakpointKind::Write, 0x02F0, 0x030F);
+        breakpoint.condition = Some(Expression::parse("VALUE > 3").unwrap());
+        debugger.breakpoints.push(breakpoint);
+
+        let hit = run_to_hit(&mut debugger, &mut cpu);
+        assert_eq!(hit.to_string(), "Write $04 to $0300 at PC $8003");
+        assert_eq!(cpu.program_counter, 0x8006);
+    }
+
+    #[test]
+    fn ppu_watchpoint() {
+        let mut cpu = CPU::new(cartridge(PROGRAM));
+        let mut debugger = Debugger::new();
+        debugger.breakpoints.push(Breakpoint::new(BreakpointKind::PpuRead, 0x0000, 0x0FFF));
+
+        let hit = run_to_hit(&mut debugger, &mut cpu);
+        assert_eq!(hit.kind, BreakpointKind::PpuRead);
+        assert!((0x0010..=0x0011).contains(&hit.addr));
+        assert_eq!(hit.pc, 0x8017);
+    }
+
+    const SUBROUTINES: &str = "
+            JSR outer
+            LDX #$01
+            BRK
+        outer:
+            LDA #$10
+            JSR inner
+            RTS
+        inner:
+            LDY #$20
+            RTS
+        ";
+
+    #[test]
+    fn step_over_and_out_of_subroutines() {
+        let mut cpu = CPU::new(cartridge(SUBROUTINES));
+        let mut debugger = Debugger::new();
+
+        debugger.start(&cpu, Command::StepOver);
+        assert_eq!(debugger.run(&mut cpu, 1000), None);
+        assert!(!debugger.running());
+        assert_eq!((cpu.program_counter, cpu.accumulator, cpu.y_register), (0x8003, 0x10, 0x20));
+
+        // Into outer and inner, then back out one level at a time
+        let mut cpu = CPU::new(cartridge(SUBROUTINES));
+        for _ in 0..3 {
+            debugger.step(&mut cpu);
+        }
+        assert_eq!(cpu.program_counter, 0x800C);
+
+        debugger.start(&cpu, Command::StepOut);
+        debugger.run(&mut cpu, 1000);
+        assert_eq!(cpu.program_counter, 0x800B);
+
+        debugger.start(&cpu, Command::StepOut);
+        debugger.run(&mut cpu, 1000);
+        assert_eq!(cpu.program_counter, 0x8003);
+        assert_eq!(cpu.x_register, 0x00);
+    }
+
+    #[test]
+    fn interrupts_during_a_step_are_stepped_over() {
+        let mut cpu = CPU::new(cartridge("NOP\nNOP\nBRK"));
+        // RTI as the NMI handler
+        cpu.bus.cartridge.prg_rom[0x7FF0] = 0x40;
+        cpu.bus.interrupts.set_nmi(true);
+
+        let mut debugger = Debugger::new();
+        let start = cpu.total_cycles();
+        debugger.start(&cpu, Command::StepOver);
+        debugger.run(&mut cpu, 1000);
+
+        // NOP, the NMI sequence and RTI
+        assert_eq!(cpu.total_cycles() - start, 2 + 7 + 6);
+        assert_eq!(cpu.program_counter, 0x8001);
+        assert_eq!(cpu.stack_pointer, 0xFD);
+        assert_eq!(cpu.interrupted, None);
+    }
+
+    #[test]
+    fn run_to_address_and_pause() {
+        let mut cpu = CPU::new(cartridge("loop:\nINX\nJMP loop"));
+        let mut debugger = Debugger::new();
+
+        debugger.start(&cpu, Command::RunTo(0x8000));
+        debugger.run(&mut cpu, 1000);
+        assert_eq!((cpu.program_counter, cpu.x_register), (0x8000, 0x01));
+
+        // Out of cycles before reaching the address, the command carries on with the next call
+        debugger.start(&cpu, Command::RunTo(0x9000));
+        assert_eq!(debugger.run(&mut cpu, 100), None);
+        assert!(debugger.running());
+        debugger.pause();
+        assert!(!debugger.running());
+
+        let mut breakpoint = Breakpoint::new(BreakpointKind::Execute, 0x8001, 0x8001);
+        breakpoint.hit_count = Some(3);
+        debugger.breakpoints.push(breakpoint);
+        debugger.start(&cpu, Command::Run);
+        assert!(debugger.run(&mut cpu, 1000).is_some());
+        assert!(!debugger.running());
+    }
+
+    #[test]
+    fn scanline_and_frame_steps() {
+        let mut cpu = CPU::new(cartridge("loop:\nJMP loop"));
+        let mut debugger = Debugger::new();
+
+        let scanline = cpu.bus.ppu.scanline;
+        debugger.start(&cpu, Command::StepScanline);
+        debugger.run(&mut cpu, 1000);
+        assert_eq!(cpu.bus.ppu.scanline, scanline + 1);
+        assert!(cpu.bus.ppu.cycle < 9);
+
+        debugger.start(&cpu, Command::StepFrame);
+        debugger.run(&mut cpu, 100_000);
+        assert_eq!((cpu.bus.ppu.frame, cpu.bus.ppu.scanline), (1, 0));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("$8000"), Ok((0x8000, 0x8000)));
+        assert_eq!(parse_range("2000 - 23ff"), Ok((0x2000, 0x23FF)));
+        assert_eq!(parse_range("$10000"), Err("invalid address '$10000'".to_string()));
+        assert_eq!(Breakpoint::new(BreakpointKind::Read, 0x23FF, 0x2000).range(), "$2000-$23FF");
+    }
+
+    #[test]
+    fn breakpoints_are_saved_and_loaded() {
+        let path = std::env::temp_dir().join(format!("runes-breakpoints-{}.json", std::process::id()));
+
+        let mut debugger = Debugger { path: Some(path.clone()), ..Debugger::default() };
+        let mut breakpoint = Breakpoint::new(BreakpointKind::PpuRead, 0x3F00, 0x3F1F);
+        breakpoint.condition = Some(Expression::parse("A == #$40 && X > 3").unwrap());
+        breakpoint.hit_count = Some(3);
+        breakpoint.enabled = false;
+        debugger.breakpoints.push(breakpoint);
+        debugger.breakpoints.push(Breakpoint::new(BreakpointKind::Execute, 0xC000, 0xC000));
+        debugger.save().unwrap();
+
+        let loaded = Debugger::load(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.breakpoints, debugger.breakpoints);
+        assert_eq!(loaded.breakpoints[0].to_string(), "PPU read $3F00-$3F1F if A == #$40 && X > 3 from hit 3");
+        assert!(Debugger::load(path).unwrap().breakpoints.is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::cpu::{AddressingMode, Variant};
+use crate::opcodes::Instruction;
+
+pub struct Disassembly {
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    pub instruction: &'static Instruction,
+    // Operand in assembly syntax, e.g. "$10,X" or "($FFFC)"
+    pub operand: String,
+}
+
+impl Disassembly {
+    // Address of the instruction that follows this one
+    pub fn next_addr(&self) -> u16 {
+        self.addr.wrapping_add(self.instruction.length())
+    }
+
+    // Operand bytes as a little endian word
+    pub fn word(&self) -> u16 {
+        let lo = *self.bytes.get(1).unwrap_or(&0) as u16;
+        let hi = *self.bytes.get(2).unwrap_or(&0) as u16;
+        (hi << 8) | lo
+    }
+
+    pub fn hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ")
+    }
+
+    // Instruction in assembly syntax, e.g. "LDA $10,X"
+    pub fn text(&self) -> String {
+        if self.operand.is_empty() {
+            format!("{:?}", self.instruction.operate)
+        } else {
+            format!("{:?} {}", self.instruction.operate, self.operand)
+        }
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04X}  {:<8}  {}", self.addr, self.hex(), self.text())
+    }
+}
+
+// Disassembles the instruction at addr, reading memory through peek so nothing on the bus is disturbed
+pub fn disassemble<F: Fn(u16) -> u8>(variant: Variant, peek: F, addr: u16) -> Disassembly {
+    let opcode = peek(addr);
+    let instruction = &variant.instructions()[opcode as usize];
+
+    let bytes: Vec<u8> = (0..instruction.length()).map(|i| peek(addr.wrapping_add(i))).collect();
+
+    let lo = *bytes.get(1).unwrap_or(&0);
+    let hi = *bytes.get(2).unwrap_or(&0);
+    let word = (hi as u16) << 8 | lo as u16;
+
+    let operand = match instruction.addrmode {
+        AddressingMode::IMP => String::new(),
+        AddressingMode::ACC => "A".to_string(),
+        AddressingMode::IMM => format!("#${:02X}", lo),
+        AddressingMode::ZP0 => format!("${:02X}", lo),
+        AddressingMode::ZPX => format!("${:02X},X", lo),
+        AddressingMode::ZPY => format!("${:02X},Y", lo),
+        AddressingMode::REL => format!("${:04X}", addr.wrapping_add(2).wrapping_add(lo as i8 as u16)),
+        AddressingMode::ABS => format!("${:04X}", word),
+        AddressingMode::ABX => format!("${:04X},X", word),
+        AddressingMode::ABY => format!("${:04X},Y", word),
+        AddressingMode::IND => format!("(${:04X})", word),
+        AddressingMode::IZX => format!("(${:02X},X)", lo),
+        AddressingMode::IZY => format!("(${:02X}),Y", lo),
+        AddressingMode::IZP => format!("(${:02X})", lo),
+        AddressingMode::IAX => format!("(${:04X},X)", word),
+    };
+
+    Disassembly {
+        addr,
+        bytes,
+        instruction,
+        operand,
+    }
+}
+
+// Disassembles count consecutive instructions starting at addr
+pub fn disassemble_range<F: Fn(u16) -> u8>(variant: Variant, peek: F, mut addr: u16, count: usize) -> Vec<Disassembly> {
+    let mut lines = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let line = disassemble(variant, &peek, addr);
+        addr = line.next_addr();
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disassemble_bytes(bytes: &[u8], addr: u16) -> String {
+        let peek = |a: u16| *bytes.get(a.wrapping_sub(addr) as usize).unwrap_or(&0);
+        disassemble(Variant::Ricoh2A03, peek, addr).text()
+    }
+
+    #[test]
+    fn addressing_modes() {
+        assert_eq!(disassemble_bytes(&[0xE8], 0x8000), "INX");
+        assert_eq!(disassemble_bytes(&[0x0A], 0x8000), "ASL A");
+        assert_eq!(disassemble_bytes(&[0xA9, 0x40], 0x8000), "LDA #$40");
+        assert_eq!(disassemble_bytes(&[0xA5, 0x10], 0x8000), "LDA $10");
+        assert_eq!(disassemble_bytes(&[0xB5, 0x10], 0x8000), "LDA $10,X");
+        assert_eq!(disassemble_bytes(&[0xB6, 0x10], 0x8000), "LDX $10,Y");
+        assert_eq!(disassemble_bytes(&[0x4C, 0xF5, 0xC5], 0x8000), "JMP $C5F5");
+        assert_eq!(disassemble_bytes(&[0xBD, 0x00, 0x03], 0x8000), "LDA $0300,X");
+        assert_eq!(disassemble_bytes(&[0xB9, 0x00, 0x03], 0x8000), "LDA $0300,Y");
+        assert_eq!(disassemble_bytes(&[0x6C, 0xFC, 0xFF], 0x8000), "JMP ($FFFC)");
+        assert_eq!(disassemble_bytes(&[0xA1, 0x80], 0x8000), "LDA ($80,X)");
+        assert_eq!(disassemble_bytes(&[0xB1, 0x89], 0x8000), "LDA ($89),Y");
+        assert_eq!(disassemble_bytes(&[0xA7, 0x10], 0x8000), "LAX $10");
+
+        // The 65C02's INC A and DEC A, not the NOPs they replace
+        let cmos = |bytes: [u8; 1]| disassemble(Variant::Cmos65C02, |a| bytes[a as usize], 0).text();
+        assert_eq!(cmos([0x1A]), "INC A");
+        assert_eq!(cmos([0x3A]), "DEC A");
+    }
+
+    #[test]
+    fn branch_targets_are_resolved() {
+        assert_eq!(disassemble_bytes(&[0xD0, 0x04], 0xC72F), "BNE $C735");
+        assert_eq!(disassemble_bytes(&[0x10, 0xFE], 0xC000), "BPL $C000");
+        assert_eq!(disassemble_bytes(&[0xF0, 0x80], 0x8010), "BEQ $7F92");
+    }
+
+    #[test]
+    fn range_follows_instruction_lengths() {
+        let bytes = [0xA2, 0x00, 0x86, 0x10, 0x4C, 0x00, 0x80, 0xEA];
+        let peek = |a: u16| *bytes.get(a.wrapping_sub(0x8000) as usize).unwrap_or(&0);
+
+        let lines = disassemble_range(Variant::Ricoh2A03, peek, 0x8000, 4);
+        let addrs: Vec<u16> = lines.iter().map(|line| line.addr).collect();
+
+        assert_eq!(addrs, vec![0x8000, 0x8002, 0x8004, 0x8007]);
+        assert_eq!(lines[2].to_string(), "8004  4C 00 80  JMP $8000");
+    }
+}
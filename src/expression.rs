@@ -0,0 +1,382 @@
+use std::fmt;
+
+use crate::cpu::CPU;
+use crate::memory::Memory;
+
+// Small expression language shared by breakpoint conditions and watches, e.g. `A == #$40 && X > 3`.
+//
+//   $40, #$40, 64, %0100   numbers, the # of immediate operands is allowed and ignored
+//   A X Y SP P PC          registers
+//   C Z I D V N            status flags, 0 or 1
+//   VALUE ADDR             data and address of the access that triggered a breakpoint
+//   [expr]                 byte of CPU memory at expr, read without side effects
+//   * / % + - << >> & ^ |  arithmetic and bitwise operators with C precedence
+//   == != < <= > >= && || comparisons and logic, true is 1
+//   - ! ~                  unary minus, logical not and bitwise not
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    SP,
+    P,
+    PC,
+    Flag(u8), // Bit of the status register
+    Value,
+    Addr,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnaryOp {
+    Negate,
+    Not,
+    Complement,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Mul, Div, Rem,
+    Add, Sub,
+    Shl, Shr,
+    Lt, Le, Gt, Ge,
+    Eq, Ne,
+    BitAnd, BitXor, BitOr,
+    And, Or,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Number(i64),
+    Register(Register),
+    Memory(Box<Node>),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+// Longest operators first so that `<=` is not read as `<`
+const OPERATORS: [&str; 24] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||",
+    "*", "/", "%", "+", "-", "<", ">", "&", "^", "|", "!", "~", "(", ")", "[", "]",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Expression, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, position: 0 };
+
+        let root = parser.binary(0)?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(format!("unexpected {}", describe(token)));
+        }
+
+        Ok(Expression { source: source.trim().to_string(), root })
+    }
+
+    // access is the address and data of the bus access being checked, if any
+    pub fn eval<M: Memory>(&self, cpu: &CPU<M>, access: Option<(u16, u8)>) -> i64 {
+        eval(&self.root, cpu, access)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        // After an operand % is the remainder, anywhere else it starts a binary number
+        let after_operand = matches!(tokens.last(), Some(Token::Number(_) | Token::Ident(_) | Token::Op(")" | "]")));
+
+        if c == '#' || c == '$' || (c == '%' && !after_operand) || c.is_ascii_digit() {
+            let text = rest.strip_prefix('#').unwrap_or(rest);
+            let (radix, digits) = match text.chars().next() {
+                Some('$') => (16, &text[1..]),
+                Some('%') => (2, &text[1..]),
+                _ => (10, text),
+            };
+
+            let length = digits.find(|c: char| !c.is_digit(radix)).unwrap_or(digits.len());
+            let number = i64::from_str_radix(&digits[..length], radix)
+                .map_err(|_| format!("invalid number '{}'", &rest[..rest.len() - digits.len() + length]))?;
+
+            tokens.push(Token::Number(number));
+            rest = &digits[length..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let length = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..length].to_ascii_uppercase()));
+            rest = &rest[length..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(number) => format!("number {}", number),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Op(op) => format!("'{}'", op),
+    }
+}
+
+fn binary_op(token: &Token) -> Option<(BinaryOp, u8)> {
+    let Token::Op(op) = token else { return None };
+
+    // Operator and its precedence, higher binds tighter
+    let op = match *op {
+        "||" => (BinaryOp::Or, 1),
+        "&&" => (BinaryOp::And, 2),
+        "|" => (BinaryOp::BitOr, 3),
+        "^" => (BinaryOp::BitXor, 4),
+        "&" => (BinaryOp::BitAnd, 5),
+        "==" => (BinaryOp::Eq, 6),
+        "!=" => (BinaryOp::Ne, 6),
+        "<" => (BinaryOp::Lt, 7),
+        "<=" => (BinaryOp::Le, 7),
+        ">" => (BinaryOp::Gt, 7),
+        ">=" => (BinaryOp::Ge, 7),
+        "<<" => (BinaryOp::Shl, 8),
+        ">>" => (BinaryOp::Shr, 8),
+        "+" => (BinaryOp::Add, 9),
+        "-" => (BinaryOp::Sub, 9),
+        "*" => (BinaryOp::Mul, 10),
+        "/" => (BinaryOp::Div, 10),
+        "%" => (BinaryOp::Rem, 10),
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+fn register(name: &str) -> Option<Register> {
+    let register = match name {
+        "A" => Register::A,
+        "X" => Register::X,
+        "Y" => Register::Y,
+        "SP" | "S" => Register::SP,
+        "P" => Register::P,
+        "PC" => Register::PC,
+        "C" => Register::Flag(0),
+        "Z" => Register::Flag(1),
+        "I" => Register::Flag(2),
+        "D" => Register::Flag(3),
+        "V" => Register::Flag(6),
+        "N" => Register::Flag(7),
+        "VALUE" => Register::Value,
+        "ADDR" => Register::Addr,
+        _ => return None,
+    };
+
+    Some(register)
+}
+
+// Precedence climbing parser
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Op(found)) if found == op => Ok(()),
+            Some(token) => Err(format!("expected '{}', found {}", op, describe(&token))),
+            None => Err(format!("expected '{}'", op)),
+        }
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<Node, String> {
+        let mut left = self.unary()?;
+
+        while let Some((op, precedence)) = self.tokens.get(self.position).and_then(binary_op) {
+            if precedence <= min_precedence {
+                break;
+            }
+            self.position += 1;
+            let right = self.binary(precedence)?;
+            left = Node::Binary(op, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Op("-")) => Ok(Node::Unary(UnaryOp::Negate, Box::new(self.unary()?))),
+            Some(Token::Op("!")) => Ok(Node::Unary(UnaryOp::Not, Box::new(self.unary()?))),
+            Some(Token::Op("~")) => Ok(Node::Unary(UnaryOp::Complement, Box::new(self.unary()?))),
+            Some(Token::Op("(")) => {
+                let node = self.binary(0)?;
+                self.expect(")")?;
+                Ok(node)
+            },
+            Some(Token::Op("[")) => {
+                let node = self.binary(0)?;
+                self.expect("]")?;
+                Ok(Node::Memory(Box::new(node)))
+            },
+            Some(Token::Number(number)) => Ok(Node::Number(number)),
+            Some(Token::Ident(name)) => register(&name).map(Node::Register).ok_or(format!("unknown name '{}'", name)),
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn eval<M: Memory>(node: &Node, cpu: &CPU<M>, access: Option<(u16, u8)>) -> i64 {
+    match node {
+        Node::Number(number) => *number,
+        Node::Register(register) => match register {
+            Register::A => cpu.accumulator as i64,
+            Register::X => cpu.x_register as i64,
+            Register::Y => cpu.y_register as i64,
+            Register::SP => cpu.stack_pointer as i64,
+            Register::P => cpu.status as i64,
+            Register::PC => cpu.program_counter as i64,
+            Register::Flag(bit) => ((cpu.status >> bit) & 0x01) as i64,
+            Register::Value => access.map_or(0, |(_, data)| data as i64),
+            Register::Addr => access.map_or(0, |(addr, _)| addr as i64),
+        },
+        Node::Memory(addr) => cpu.peek(eval(addr, cpu, access) as u16) as i64,
+        Node::Unary(op, operand) => {
+            let value = eval(operand, cpu, access);
+            match op {
+                UnaryOp::Negate => value.wrapping_neg(),
+                UnaryOp::Not => (value == 0) as i64,
+                UnaryOp::Complement => !value,
+            }
+        },
+        Node::Binary(BinaryOp::And, left, right) => (eval(left, cpu, access) != 0 && eval(right, cpu, access) != 0) as i64,
+        Node::Binary(BinaryOp::Or, left, right) => (eval(left, cpu, access) != 0 || eval(right, cpu, access) != 0) as i64,
+        Node::Binary(op, left, right) => {
+            let left = eval(left, cpu, access);
+            let right = eval(right, cpu, access);
+            match op {
+                BinaryOp::Mul => left.wrapping_mul(right),
+                // Dividing by zero gives zero rather than stopping the emulator
+                BinaryOp::Div => left.checked_div(right).unwrap_or(0),
+                BinaryOp::Rem => left.checked_rem(right).unwrap_or(0),
+                BinaryOp::Add => left.wrapping_add(right),
+                BinaryOp::Sub => left.wrapping_sub(right),
+                BinaryOp::Shl => u32::try_from(right).ok().and_then(|shift| left.checked_shl(shift)).unwrap_or(0),
+                BinaryOp::Shr => u32::try_from(right).ok().and_then(|shift| left.checked_shr(shift)).unwrap_or(0),
+                BinaryOp::Lt => (left < right) as i64,
+                BinaryOp::Le => (left <= right) as i64,
+                BinaryOp::Gt => (left > right) as i64,
+                BinaryOp::Ge => (left >= right) as i64,
+                BinaryOp::Eq => (left == right) as i64,
+                BinaryOp::Ne => (left != right) as i64,
+                BinaryOp::BitAnd => left & right,
+                BinaryOp::BitXor => left ^ right,
+                BinaryOp::BitOr => left | right,
+                BinaryOp::And | BinaryOp::Or => unreachable!(),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::run;
+
+    fn eval_str(source: &str) -> i64 {
+        let cpu = run("
+            LDA #$40
+            LDX #$05
+            LDY #$02
+            STA $10
+            LDA #$03
+            STA $11
+            LDA #$40
+            SEC
+        ");
+        Expression::parse(source).unwrap().eval(&cpu, Some((0x0300, 0x12)))
+    }
+
+    #[test]
+    fn numbers_and_registers() {
+        assert_eq!(eval_str("$FF"), 255);
+        assert_eq!(eval_str("#$40"), 64);
+        assert_eq!(eval_str("%101"), 5);
+        assert_eq!(eval_str("12"), 12);
+        assert_eq!(eval_str("a"), 0x40);
+        assert_eq!(eval_str("X + Y"), 7);
+        assert_eq!(eval_str("C"), 1);
+        assert_eq!(eval_str("Z"), 0);
+        assert_eq!(eval_str("VALUE"), 0x12);
+        assert_eq!(eval_str("ADDR"), 0x0300);
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval_str("1 + 2 * 3"), 7);
+        assert_eq!(eval_str("(1 + 2) * 3"), 9);
+        assert_eq!(eval_str("1 << 4 | 1"), 17);
+        assert_eq!(eval_str("-2 + 5"), 3);
+        assert_eq!(eval_str("!0 + ~0"), 0);
+        assert_eq!(eval_str("10 / 0"), 0);
+        assert_eq!(eval_str("7 % 3"), 1);
+        assert_eq!(eval_str("X%2 + [$10] % %11"), 2);
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!(eval_str("A == #$40 && X > 3"), 1);
+        assert_eq!(eval_str("A == #$40 && X > 5"), 0);
+        assert_eq!(eval_str("A != $40 || Y <= 2"), 1);
+    }
+
+    #[test]
+    fn memory() {
+        assert_eq!(eval_str("[$10]"), 0x40);
+        assert_eq!(eval_str("[$10] + [$11] * 256"), 0x0340);
+        assert_eq!(eval_str("[[$11] + $0D]"), 0x40);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Expression::parse("A ==").unwrap_err(), "unexpected end of expression");
+        assert_eq!(Expression::parse("A == Q").unwrap_err(), "unknown name 'Q'");
+        assert_eq!(Expression::parse("[A").unwrap_err(), "expected ']'");
+        assert_eq!(Expression::parse("A 1").unwrap_err(), "unexpected number 1");
+        assert_eq!(Expression::parse("A @ 1").unwrap_err(), "unexpected character '@'");
+        assert_eq!(Expression::parse("$G").unwrap_err(), "invalid number '$'");
+    }
+}
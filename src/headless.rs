@@ -0,0 +1,180 @@
+use std::fmt;
+
+use crate::cartridge::Cartridge;
+use crate::cpu::CPU;
+use crate::trace::trace;
+
+// nestest.nes runs every test without a PPU when started at $C000 (automation mode)
+const NESTEST_START: u16 = 0xC000;
+// Final RTS of the automation run, the last line of nestest.log
+const NESTEST_END: u16 = 0xC66E;
+// Safety net in case the CPU runs away instead of reaching the end
+const NESTEST_MAX_INSTRUCTIONS: usize = 10000;
+
+pub struct NestestResult {
+    pub trace: Vec<String>,
+    // nestest stores the number of the first failed test in $02 (official) and $03 (unofficial)
+    pub official_result: u8,
+    pub unofficial_result: u8,
+}
+
+#[derive(Debug)]
+pub struct Divergence {
+    pub line: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Trace diverges at line {}\nExpected: {}\nActual:   {}", self.line, self.expected, self.actual)
+    }
+}
+
+pub fn run_nestest(rom: &str) -> Result<NestestResult, String> {
+    let mut cpu = CPU::new(Cartridge::new(rom)?);
+    cpu.program_counter = NESTEST_START;
+
+    let mut lines = Vec::new();
+
+    loop {
+        lines.push(trace(&cpu));
+
+        if cpu.program_counter == NESTEST_END {
+            break;
+        }
+
+        if lines.len() >= NESTEST_MAX_INSTRUCTIONS {
+            return Err(format!("nestest did not finish within {} instructions", NESTEST_MAX_INSTRUCTIONS));
+        }
+
+        cpu.step();
+
+        if let Some(addr) = cpu.jammed {
+            return Err(format!("CPU jammed at ${:04X}", addr));
+        }
+    }
+
+    Ok(NestestResult {
+        trace: lines,
+        official_result: cpu.read(0x0002, true),
+        unofficial_result: cpu.read(0x0003, true),
+    })
+}
+
+// Compares a trace against a reference log and reports the first line that differs
+pub fn compare_trace(actual: &[String], reference: &str) -> Result<(), Divergence> {
+    let expected: Vec<&str> = reference.lines().map(|line| line.trim_end()).collect();
+
+    for (index, (expected_line, actual_line)) in expected.iter().zip(actual.iter()).enumerate() {
+        if expected_line != actual_line {
+            return Err(Divergence {
+                line: index + 1,
+                expected: expected_line.to_string(),
+                actual: actual_line.clone(),
+            });
+        }
+    }
+
+    if expected.len() != actual.len() {
+        let line = expected.len().min(actual.len());
+        return Err(Divergence {
+            line: line + 1,
+            expected: expected.get(line).map_or("<end of log>".to_string(), |l| l.to_string()),
+            actual: actual.get(line).map_or("<end of trace>".to_string(), |l| l.clone()),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::cartridge;
+
+    const NESTEST_ROM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/nestest.nes");
+    const NESTEST_LOG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/nestest.log");
+
+    #[test]
+    fn nestest() {
+        let result = run_nestest(NESTEST_ROM).unwrap();
+
+        assert_eq!(
+            result.trace[0],
+            "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7"
+        );
+        assert_eq!(result.official_result, 0x00, "official opcode test {:#04X} failed", result.official_result);
+        assert_eq!(result.unofficial_result, 0x00, "unofficial opcode test {:#04X} failed", result.unofficial_result);
+    }
+
+    #[test]
+    #[ignore = "needs Nintendulator's nestest.log in tests/fixtures, it isn't distributed with the ROM"]
+    fn nestest_trace() {
+        let result = run_nestest(NESTEST_ROM).unwrap();
+        let reference = std::fs::read_to_string(NESTEST_LOG).unwrap_or_else(|error| panic!("{}: {}", NESTEST_LOG, error));
+
+        if let Err(divergence) = compare_trace(&result.trace, &reference) {
+            panic!("{}", divergence);
+        }
+    }
+
+    #[test]
+    fn compare_trace_reports_first_divergence() {
+        let actual = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+
+        assert!(compare_trace(&actual, "A\r\nB\r\nC\r\n").is_ok());
+
+        let divergence = compare_trace(&actual, "A\nX\nC\n").unwrap_err();
+        assert_eq!(divergence.line, 2);
+        assert_eq!(divergence.expected, "X");
+        assert_eq!(divergence.actual, "B");
+
+        let divergence = compare_trace(&actual, "A\nB\n").unwrap_err();
+        assert_eq!(divergence.line, 3);
+        assert_eq!(divergence.expected, "<end of log>");
+    }
+
+    // Traces a program that fills page 2 and copies it to OAM, returning the trace and the CPU
+    fn trace_oam_dma(setup: &str) -> (Vec<String>, CPU) {
+        let mut cpu = CPU::new(cartridge(&format!("
+                {}
+                LDX #$00
+            fill:
+                TXA
+                STA $0200,X
+                INX
+                BNE fill
+                LDA #$02
+                STA $4014
+                NOP
+            ", setup)));
+
+        let mut lines = Vec::new();
+        while cpu.peek(cpu.program_counter) != 0x00 {
+            lines.push(trace(&cpu));
+            cpu.step();
+        }
+        (lines, cpu)
+    }
+
+    // Cycles between the STA $4014 line and the next one of the trace
+    fn dma_cycles(lines: &[String]) -> u64 {
+        let cycle = |line: &String| line.rsplit("CYC:").next().unwrap().parse::<u64>().unwrap();
+        let index = lines.iter().position(|line| line.contains("STA $4014")).unwrap();
+        cycle(&lines[index + 1]) - cycle(&lines[index])
+    }
+
+    #[test]
+    fn oam_dma_stalls_the_cpu() {
+        let (lines, cpu) = trace_oam_dma("");
+        assert_eq!(cpu.bus.ppu.oam.to_vec(), (0..=255).collect::<Vec<u8>>());
+
+        // STA absolute takes 4 cycles, here its write lands on an odd cycle and the DMA takes 514
+        assert_eq!(dma_cycles(&lines), 4 + 514);
+
+        // A 3 cycle instruction in front moves it to an even cycle
+        let (lines, _) = trace_oam_dma("LDA $00");
+        assert_eq!(dma_cycles(&lines), 4 + 513);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+// Frames a changed byte stays highlighted for
+pub const CHANGE_FRAMES: u8 = 60;
+
+// Text column of the hex editor. Games rarely store text as ASCII, a table file maps their bytes to characters.
+// Thingy/FCEUX .tbl format, one "XX=text" line per byte; comments start with ';' or '#'.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    entries: HashMap<u8, String>,
+}
+
+impl Table {
+    pub fn parse(text: &str) -> Result<Table, String> {
+        let mut entries = HashMap::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.trim().is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            let (byte, value) = line.split_once('=').ok_or(format!("line {}: expected XX=text", index + 1))?;
+            let byte = u8::from_str_radix(byte.trim(), 16).map_err(|_| format!("line {}: invalid byte '{}'", index + 1, byte.trim()))?;
+            entries.insert(byte, value.to_string());
+        }
+
+        Ok(Table { entries })
+    }
+
+    // What the text column shows for byte, a dot when the table doesn't know it
+    pub fn text(&self, byte: u8) -> String {
+        self.entries.get(&byte).cloned().unwrap_or(".".to_string())
+    }
+
+    // Bytes spelling text, matching the longest entries first
+    pub fn encode(&self, mut text: &str) -> Result<Vec<u8>, String> {
+        let mut entries: Vec<(&u8, &String)> = self.entries.iter().filter(|(_, value)| !value.is_empty()).collect();
+        entries.sort_by_key(|(byte, value)| (std::cmp::Reverse(value.len()), **byte));
+
+        let mut bytes = Vec::new();
+        while !text.is_empty() {
+            let (byte, value) = entries.iter().find(|(_, value)| text.starts_with(value.as_str()))
+                .ok_or(format!("no table entry for '{}'", text.chars().next().unwrap()))?;
+            bytes.push(**byte);
+            text = &text[value.len()..];
+        }
+
+        Ok(bytes)
+    }
+}
+
+// Printable ASCII or a dot
+pub fn ascii(byte: u8) -> char {
+    if (0x20..0x7F).contains(&byte) { byte as char } else { '.' }
+}
+
+// A search pattern: hex bytes with ?? for any byte ("A9 ?? 8D 00 20"), or "quoted text" encoded with the table or ASCII
+pub fn parse_pattern(text: &str, table: Option<&Table>) -> Result<Vec<Option<u8>>, String> {
+    let text = text.trim();
+
+    if let Some(quoted) = text.strip_prefix('"').and_then(|text| text.strip_suffix('"')) {
+        let bytes = match table {
+            Some(table) => table.encode(quoted)?,
+            None => quoted.bytes().collect(),
+        };
+        if bytes.is_empty() {
+            return Err("nothing to search for".to_string());
+        }
+        return Ok(bytes.into_iter().map(Some).collect());
+    }
+
+    let digits: String = text.split_whitespace().collect();
+    // Pairs are sliced by byte, which only lines up with characters in ASCII
+    if digits.is_empty() || !digits.is_ascii() || !digits.len().is_multiple_of(2) {
+        return Err("expected hex bytes like A9 ?? 8D".to_string());
+    }
+
+    (0..digits.len()).step_by(2).map(|index| match &digits[index..index + 2] {
+        "??" => Ok(None),
+        byte => u8::from_str_radix(byte, 16).map(Some).map_err(|_| format!("invalid byte '{}'", byte)),
+    }).collect()
+}
+
+// Start addresses of every match in the CPU address space
+pub fn search<F: Fn(u16) -> u8>(peek: F, pattern: &[Option<u8>]) -> Vec<u16> {
+    let memory: Vec<u8> = (0..=0xFFFF).map(peek).collect();
+
+    memory.windows(pattern.len()).enumerate()
+        .filter(|(_, window)| window.iter().zip(pattern).all(|(byte, expected)| expected.is_none_or(|expected| expected == *byte)))
+        .map(|(addr, _)| addr as u16)
+        .collect()
+}
+
+// Remembers the address space from one frame to the next to highlight the bytes that changed
+pub struct ChangeTracker {
+    previous: Vec<u8>,
+    age: Vec<u8>, // Frames since the byte last changed, CHANGE_FRAMES when it hasn't recently
+}
+
+impl Default for ChangeTracker {
+    fn default() -> Self {
+        ChangeTracker { previous: Vec::new(), age: vec![CHANGE_FRAMES; 0x10000] }
+    }
+}
+
+impl ChangeTracker {
+    // Called once per frame, the first call only takes the snapshot
+    pub fn update<F: Fn(u16) -> u8>(&mut self, peek: F) {
+        let current: Vec<u8> = (0..=0xFFFF).map(peek).collect();
+
+        for (addr, age) in self.age.iter_mut().enumerate() {
+            if self.previous.get(addr).is_some_and(|previous| *previous != current[addr]) {
+                *age = 0;
+            } else if *age < CHANGE_FRAMES {
+                *age += 1;
+            }
+        }
+
+        self.previous = current;
+    }
+
+    // Frames since the byte at addr changed, if it did recently
+    pub fn age(&self, addr: u16) -> Option<u8> {
+        Some(self.age[addr as usize]).filter(|age| *age < CHANGE_FRAMES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_files() {
+        let table = Table::parse("; Dragon Warrior-ish\n0A=A\n0B=B\n24= \n60=ing\n").unwrap();
+
+        assert_eq!(table.text(0x0A), "A");
+        assert_eq!(table.text(0x24), " ");
+        assert_eq!(table.text(0xFF), ".");
+        assert_eq!(table.encode("BAing A"), Ok(vec![0x0B, 0x0A, 0x60, 0x24, 0x0A]));
+        assert_eq!(table.encode("C"), Err("no table entry for 'C'".to_string()));
+
+        assert_eq!(Table::parse("0A"), Err("line 1: expected XX=text".to_string()));
+        assert_eq!(Table::parse("\n0G=x"), Err("line 2: invalid byte '0G'".to_string()));
+    }
+
+    #[test]
+    fn patterns_and_search() {
+        assert_eq!(parse_pattern("A9 ?? 8d", None), Ok(vec![Some(0xA9), None, Some(0x8D)]));
+        assert_eq!(parse_pattern("\"NES\"", None), Ok(vec![Some(0x4E), Some(0x45), Some(0x53)]));
+        assert!(parse_pattern("A9 8", None).is_err());
+        assert!(parse_pattern("ZZ", None).is_err());
+        assert!(parse_pattern("aé1", None).is_err());
+        assert!(parse_pattern("\"\"", None).is_err());
+
+        let table = Table::parse("0A=A\n0B=B").unwrap();
+        assert_eq!(parse_pattern("\"AB\"", Some(&table)), Ok(vec![Some(0x0A), Some(0x0B)]));
+
+        let memory = |addr: u16| match addr {
+            0x0300 => 0xA9,
+            0x0301 => 0x01,
+            0x0302 => 0x8D,
+            0xC000 => 0xA9,
+            0xC002 => 0x8D,
+            _ => 0x00,
+        };
+        assert_eq!(search(memory, &[Some(0xA9), None, Some(0x8D)]), vec![0x0300, 0xC000]);
+        assert_eq!(search(memory, &[Some(0xA9), Some(0x01)]), vec![0x0300]);
+    }
+
+    #[test]
+    fn changed_bytes_fade() {
+        let mut tracker = ChangeTracker::default();
+        tracker.update(|_| 0x00);
+        assert_eq!(tracker.age(0x0010), None);
+
+        tracker.update(|addr| if addr == 0x0010 { 0x01 } else { 0x00 });
+        assert_eq!(tracker.age(0x0010), Some(0));
+        assert_eq!(tracker.age(0x0011), None);
+
+        for _ in 0..CHANGE_FRAMES {
+            tracker.update(|addr| if addr == 0x0010 { 0x01 } else { 0x00 });
+        }
+        assert_eq!(tracker.age(0x0010), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+// Devices that can pull the shared IRQ line low
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IrqSource {
+    FrameCounter = (1 << 0), // APU frame counter
+    Dmc = (1 << 1), // APU delta modulation channel
+    Mapper = (1 << 2), // Cartridge mapper
+    External = (1 << 3), // Expansion port
+}
+
+pub const IRQ_SOURCES: [IrqSource; 4] = [IrqSource::FrameCounter, IrqSource::Dmc, IrqSource::Mapper, IrqSource::External];
+
+impl fmt::Display for IrqSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IrqSource::FrameCounter => write!(f, "APU frame counter"),
+            IrqSource::Dmc => write!(f, "APU DMC"),
+            IrqSource::Mapper => write!(f, "Mapper"),
+            IrqSource::External => write!(f, "External"),
+        }
+    }
+}
+
+// IRQ is level triggered, the line stays asserted for as long as any source holds it.
+// NMI is edge triggered, only the moment the line becomes asserted raises an interrupt.
+#[derive(Default)]
+pub struct InterruptController {
+    irq_sources: u8,
+    nmi_line: bool,
+    nmi_edge: bool,
+}
+
+impl InterruptController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_irq(&mut self, source: IrqSource, asserted: bool) {
+        if asserted {
+            self.irq_sources |= source as u8;
+        } else {
+            self.irq_sources &= !(source as u8);
+        }
+    }
+
+    pub fn irq(&self) -> bool {
+        self.irq_sources != 0
+    }
+
+    // Sources currently asserting IRQ, for the debugger
+    pub fn irq_sources(&self) -> Vec<IrqSource> {
+        IRQ_SOURCES.iter().copied().filter(|source| self.irq_sources & *source as u8 != 0).collect()
+    }
+
+    pub fn set_nmi(&mut self, asserted: bool) {
+        if asserted && !self.nmi_line {
+            self.nmi_edge = true;
+        }
+        self.nmi_line = asserted;
+    }
+
+    pub fn nmi(&self) -> bool {
+        self.nmi_line
+    }
+
+    // Returns true, and acknowledges it, when the NMI line was asserted since the last poll
+    pub fn poll_nmi(&mut self) -> bool {
+        let edge = self.nmi_edge;
+        self.nmi_edge = false;
+        edge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn irq_is_held_by_any_source() {
+        let mut interrupts = InterruptController::new();
+        assert!(!interrupts.irq());
+
+        interrupts.set_irq(IrqSource::Mapper, true);
+        interrupts.set_irq(IrqSource::FrameCounter, true);
+        assert_eq!(interrupts.irq_sources(), vec![IrqSource::FrameCounter, IrqSource::Mapper]);
+
+        interrupts.set_irq(IrqSource::Mapper, false);
+        assert!(interrupts.irq());
+
+        interrupts.set_irq(IrqSource::FrameCounter, false);
+        assert!(!interrupts.irq());
+    }
+
+    #[test]
+    fn nmi_triggers_on_the_edge() {
+        let mut interrupts = InterruptController::new();
+
+        interrupts.set_nmi(true);
+        interrupts.set_nmi(true);
+        assert!(interrupts.poll_nmi());
+        assert!(!interrupts.poll_nmi());
+
+        // Holding the line does not raise another one, releasing and asserting it again does
+        interrupts.set_nmi(true);
+        assert!(!interrupts.poll_nmi());
+        interrupts.set_nmi(false);
+        interrupts.set_nmi(true);
+        assert!(interrupts.poll_nmi());
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use eframe::egui::Key;
+use serde_json::{json, Map, Value};
+
+use crate::controller::BUTTONS;
+use crate::storage;
+
+// Keys that can be mapped to buttons. egui can't look a key up by its name, saved mappings are matched against these.
+const KEYS: [Key; 52] = [
+    Key::ArrowUp, Key::ArrowDown, Key::ArrowLeft, Key::ArrowRight,
+    Key::Enter, Key::Tab, Key::Backspace, Key::Insert, Key::Delete, Key::Home, Key::End, Key::PageUp, Key::PageDown,
+    Key::Minus, Key::PlusEquals,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Space,
+];
+
+pub fn key(name: &str) -> Option<Key> {
+    KEYS.iter().copied().find(|key| key.name() == name)
+}
+
+// Whether key can be picked for a button, Space and R stay with stepping and reset
+pub fn mappable(key: Key) -> bool {
+    KEYS.contains(&key) && key != Key::Space && key != Key::R
+}
+
+// Keys of A, B, Select, Start, Up, Down, Left and Right
+pub type Mapping = [Key; 8];
+
+// Keyboard keys of the buttons of every player
+pub struct KeyMap {
+    pub players: Vec<Mapping>,
+    pub path: Option<PathBuf>, // Where the mapping is saved
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap {
+            players: vec![
+                [Key::X, Key::Z, Key::Backspace, Key::Enter, Key::ArrowUp, Key::ArrowDown, Key::ArrowLeft, Key::ArrowRight],
+                [Key::G, Key::F, Key::Q, Key::E, Key::W, Key::S, Key::A, Key::D],
+                [Key::O, Key::U, Key::Y, Key::P, Key::I, Key::K, Key::J, Key::L],
+                [Key::PageUp, Key::Insert, Key::Minus, Key::PlusEquals, Key::Home, Key::End, Key::Delete, Key::PageDown],
+            ],
+            path: None,
+        }
+    }
+}
+
+impl KeyMap {
+    // Mapping saved at path, the defaults for what wasn't saved
+    pub fn load(path: PathBuf) -> Result<KeyMap, String> {
+        let mut keymap = KeyMap { path: Some(path.clone()), ..KeyMap::default() };
+
+        let Some(value) = storage::read_json(&path)? else { return Ok(keymap) };
+        for (mapping, saved) in keymap.players.iter_mut().zip(value["players"].as_array().into_iter().flatten()) {
+            for (key, button) in mapping.iter_mut().zip(BUTTONS) {
+                if let Some(name) = saved[button.to_string()].as_str() {
+                    *key = self::key(name).ok_or(format!("{}: unknown key '{}'", path.display(), name))?;
+                }
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+
+        let players: Vec<Value> = self.players.iter().map(|mapping| {
+            Value::Object(BUTTONS.iter().zip(mapping).map(|(button, key)| (button.to_string(), json!(key.name()))).collect::<Map<_, _>>())
+        }).collect();
+
+        storage::write_json(path, &json!({ "players": players }))
+    }
+
+    // Buttons player holds down, a bit per Button
+    pub fn buttons<F: Fn(Key) -> bool>(&self, player: usize, down: F) -> u8 {
+        BUTTONS.iter().zip(&self.players[player])
+            .filter(|(_, key)| down(**key))
+            .fold(0, |buttons, (button, _)| buttons | button.mask())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::Button;
+    use std::fs;
+
+    #[test]
+    fn buttons_held() {
+        let keymap = KeyMap::default();
+        let down = |key: Key| key == Key::X || key == Key::ArrowLeft || key == Key::W;
+
+        assert_eq!(keymap.buttons(0, down), Button::A.mask() | Button::Left.mask());
+        assert_eq!(keymap.buttons(1, down), Button::Up.mask());
+        assert_eq!(keymap.buttons(3, |key| key == Key::End), Button::Down.mask());
+        assert_eq!(key("Left"), Some(Key::ArrowLeft));
+        assert!(!mappable(Key::Space));
+    }
+
+    #[test]
+    fn mappings_are_saved_and_loaded() {
+        let path = std::env::temp_dir().join(format!("runes-keymap-{}.json", std::process::id()));
+
+        let mut keymap = KeyMap { path: Some(path.clone()), ..KeyMap::default() };
+        keymap.players[1][0] = Key::Num1;
+        keymap.save().unwrap();
+
+        let loaded = KeyMap::load(path.clone()).unwrap();
+        assert_eq!(loaded.players, keymap.players);
+
+        // Buttons missing from the file keep their default keys
+        fs::write(&path, r#"{ "players": [{ "Start": "P" }] }"#).unwrap();
+        let loaded = KeyMap::load(path.clone()).unwrap();
+        assert_eq!(loaded.players[0][3], Key::P);
+        assert_eq!(loaded.players[0][0], Key::X);
+        // Mappings saved before players 3 and 4 existed
+        assert_eq!(loaded.players[2][0], Key::O);
+
+        fs::write(&path, r#"{ "players": [{ "A": "Shift" }] }"#).unwrap();
+        assert!(KeyMap::load(path.clone()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+
+use crate::cpu::{Variant, CPU};
+use crate::memory::{FlatMemory, Memory};
+use crate::opcodes::Opcode;
+
+// Klaus Dormann's 6502 test suites (https://github.com/Klaus2m5/6502_65C02_functional_tests).
+// Both are 64 KiB images that start at $0400 and end in a trap, a jump or branch to itself.
+// Reaching the trap at the success address means every test passed.
+pub const START: u16 = 0x0400;
+pub const FUNCTIONAL_TEST_SUCCESS: u16 = 0x3469;
+pub const INTERRUPT_TEST_SUCCESS: u16 = 0x06F5;
+
+// The suites keep the number of the running test here
+const TEST_CASE: u16 = 0x0200;
+
+// The interrupt test raises IRQ and NMI by writing to this feedback register
+const INTERRUPT_PORT: u16 = 0xBFFC;
+const IRQ_BIT: u8 = 1 << 0;
+const NMI_BIT: u8 = 1 << 1;
+
+// Instructions a run gets before it counts as stuck, the functional test needs well under this
+const MAX_INSTRUCTIONS: u64 = 100_000_000;
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    // The success trap was reached
+    Passed,
+    // The 2A03 has no decimal mode, so the run stops as soon as the decimal tests start
+    DecimalSkipped { test_case: u8 },
+}
+
+#[derive(Debug)]
+pub struct TrapReport {
+    pub addr: u16,
+    pub jammed: bool, // The CPU hit a JAM opcode at addr instead of trapping
+    pub test_case: u8,
+    pub accumulator: u8,
+    pub x_register: u8,
+    pub y_register: u8,
+    pub stack_pointer: u8,
+    pub status: u8,
+    pub instructions: u64,
+}
+
+impl fmt::Display for TrapReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at ${:04X} in test case ${:02X} after {} instructions (A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X})",
+            if self.jammed { "Jammed" } else { "Trapped" }, self.addr, self.test_case, self.instructions,
+            self.accumulator, self.x_register, self.y_register, self.status, self.stack_pointer,
+        )
+    }
+}
+
+// Flat memory with the interrupt test's feedback register mapped in
+pub struct InterruptTestMemory {
+    pub memory: FlatMemory,
+    nmi_pending: bool,
+}
+
+impl InterruptTestMemory {
+    pub fn new(memory: FlatMemory) -> Self {
+        InterruptTestMemory {
+            memory,
+            nmi_pending: false,
+        }
+    }
+
+}
+
+impl Memory for InterruptTestMemory {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.memory.mem_read(addr)
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        // NMI is edge triggered on the rising edge of the bit
+        if addr == INTERRUPT_PORT && data & NMI_BIT != 0 && self.memory.mem_peek(addr) & NMI_BIT == 0 {
+            self.nmi_pending = true;
+        }
+        self.memory.mem_write(addr, data);
+    }
+
+    fn mem_peek(&self, addr: u16) -> u8 {
+        self.memory.mem_peek(addr)
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        let nmi = self.nmi_pending;
+        self.nmi_pending = false;
+        nmi
+    }
+
+    // IRQ is level triggered, it stays asserted as long as the bit is set
+    fn irq(&self) -> bool {
+        self.memory.mem_peek(INTERRUPT_PORT) & IRQ_BIT != 0
+    }
+}
+
+pub fn run_functional_test(variant: Variant, image: &[u8]) -> Result<Outcome, TrapReport> {
+    let mut memory = FlatMemory::new();
+    memory.load(0x0000, image);
+
+    let mut cpu = CPU::with_memory(memory);
+    cpu.variant = variant;
+    run(&mut cpu, FUNCTIONAL_TEST_SUCCESS, false)
+}
+
+// Test case of the first decimal ADC or SBC, where the 2A03 run has to stop
+pub fn decimal_test_case(variant: Variant, image: &[u8]) -> Result<u8, TrapReport> {
+    let mut memory = FlatMemory::new();
+    memory.load(0x0000, image);
+
+    let mut cpu = CPU::with_memory(memory);
+    cpu.variant = variant;
+    match run(&mut cpu, FUNCTIONAL_TEST_SUCCESS, true)? {
+        Outcome::DecimalSkipped { test_case } => Ok(test_case),
+        Outcome::Passed => Err(report(&cpu, 0)),
+    }
+}
+
+pub fn run_interrupt_test(image: &[u8]) -> Result<Outcome, TrapReport> {
+    let mut memory = FlatMemory::new();
+    memory.load(0x0000, image);
+
+    let mut cpu = CPU::with_memory(InterruptTestMemory::new(memory));
+    run(&mut cpu, INTERRUPT_TEST_SUCCESS, false)
+}
+
+// Runs from START until the program traps, or until the decimal tests with stop_at_decimal
+fn run<M: Memory>(cpu: &mut CPU<M>, success: u16, stop_at_decimal: bool) -> Result<Outcome, TrapReport> {
+    cpu.power_on();
+    cpu.program_counter = START;
+
+    for instructions in 0..MAX_INSTRUCTIONS {
+        let opcode = cpu.peek(cpu.program_counter);
+        let decimal = cpu.status & 0x08 != 0 && (stop_at_decimal || !cpu.variant.has_decimal_mode());
+        if decimal && matches!(cpu.variant.instructions()[opcode as usize].operate, Opcode::ADC | Opcode::SBC) {
+            return Ok(Outcome::DecimalSkipped { test_case: cpu.peek(TEST_CASE) });
+        }
+
+        let pc = cpu.program_counter;
+        cpu.step();
+
+        if cpu.jammed.is_some() {
+            return Err(report(cpu, instructions));
+        }
+
+        if cpu.program_counter == pc {
+            if pc == success {
+                return Ok(Outcome::Passed);
+            }
+            return Err(report(cpu, instructions));
+        }
+    }
+
+    Err(report(cpu, MAX_INSTRUCTIONS))
+}
+
+fn report<M: Memory>(cpu: &CPU<M>, instructions: u64) -> TrapReport {
+    TrapReport {
+        addr: cpu.jammed.unwrap_or(cpu.program_counter),
+        jammed: cpu.jammed.is_some(),
+        test_case: cpu.peek(TEST_CASE),
+        accumulator: cpu.accumulator,
+        x_register: cpu.x_register,
+        y_register: cpu.y_register,
+        stack_pointer: cpu.stack_pointer,
+        status: cpu.status,
+        instructions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    const FUNCTIONAL_TEST: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/6502_functional_test.bin");
+    const INTERRUPT_TEST: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/6502_interrupt_test.bin");
+
+    fn image(source: &str) -> Vec<u8> {
+        let mut image = vec![0x00; 0x10000];
+        let program = assemble(source, START).unwrap();
+        image[START as usize..START as usize + program.len()].copy_from_slice(&program);
+        image
+    }
+
+    #[test]
+    #[ignore = "needs 6502_functional_test.bin in tests/fixtures, it isn't in the tree"]
+    fn functional_test() {
+        let image = std::fs::read(FUNCTIONAL_TEST).unwrap_or_else(|error| panic!("{}: {}", FUNCTIONAL_TEST, error));
+
+        // The 2A03 has to get as far as the decimal tests, the same test case the NMOS 6502 reaches them at
+        let decimal = decimal_test_case(Variant::Nmos6502, &image).unwrap_or_else(|report| panic!("{}", report));
+        match run_functional_test(Variant::Ricoh2A03, &image) {
+            Ok(outcome) => assert_eq!(outcome, Outcome::DecimalSkipped { test_case: decimal }),
+            Err(report) => panic!("{}", report),
+        }
+
+        // The NMOS 6502 also has to get through the decimal mode tests
+        match run_functional_test(Variant::Nmos6502, &image) {
+            Ok(outcome) => assert_eq!(outcome, Outcome::Passed),
+            Err(report) => panic!("{}", report),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs 6502_interrupt_test.bin in tests/fixtures, it isn't in the tree"]
+    fn interrupt_test() {
+        let image = std::fs::read(INTERRUPT_TEST).unwrap_or_else(|error| panic!("{}: {}", INTERRUPT_TEST, error));
+
+        if let Err(report) = run_interrupt_test(&image) {
+            panic!("{}", report);
+        }
+    }
+
+    #[test]
+    fn reports_failing_trap() {
+        let report = run_functional_test(Variant::Ricoh2A03, &image("
+                LDA #$07
+                STA $0200
+                LDX #$12
+            trap:
+                BNE trap
+        ")).unwrap_err();
+
+        assert_eq!(report.addr, 0x0407);
+        assert_eq!(report.test_case, 0x07);
+        assert_eq!(report.x_register, 0x12);
+    }
+
+    #[test]
+    fn reports_jams() {
+        let report = run_functional_test(Variant::Ricoh2A03, &image("
+                LDA #$03
+                STA $0200
+                .byte $02
+        ")).unwrap_err();
+
+        assert!(report.jammed);
+        assert_eq!(report.addr, 0x0405);
+        assert_eq!(report.to_string(), "Jammed at $0405 in test case $03 after 2 instructions (A:03 X:00 Y:00 P:24 SP:FD)");
+    }
+
+    #[test]
+    fn stops_at_decimal_mode_tests() {
+        let program = image("
+                LDA #$2A
+                STA $0200
+                SED
+                ADC #$01
+            trap:
+                JMP trap
+        ");
+
+        assert_eq!(run_functional_test(Variant::Ricoh2A03, &program).unwrap(), Outcome::DecimalSkipped { test_case: 0x2A });
+        assert_eq!(run_functional_test(Variant::Nmos6502, &program).unwrap_err().addr, 0x0408);
+        assert_eq!(decimal_test_case(Variant::Nmos6502, &program).unwrap(), 0x2A);
+    }
+
+    #[test]
+    fn feedback_register_raises_interrupts() {
+        let mut image = image("
+                CLI
+                LDA #$01
+                STA $BFFC
+            trap:
+                JMP trap
+            irq:
+                LDA #$02
+                STA $BFFC
+                LDX #$FF
+                RTI
+            nmi:
+                LDY #$EE
+                LDA #$00
+                STA $BFFC
+                RTI
+        ");
+        // Vectors point at the handlers assembled above
+        image[0xFFFA..0xFFFC].copy_from_slice(&[0x11, 0x04]);
+        image[0xFFFE..0x10000].copy_from_slice(&[0x09, 0x04]);
+
+        let report = run_interrupt_test(&image).unwrap_err();
+
+        assert_eq!(report.addr, 0x0406);
+        assert_eq!(report.x_register, 0xFF);
+        assert_eq!(report.y_register, 0xEE);
+    }
+}
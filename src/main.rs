@@ -5,17 +5,135 @@ pub mod opcodes;
 pub mod ui;
 pub mod cartridge;
 pub mod renderer;
+pub mod trace;
+pub mod disassembler;
+pub mod assembler;
+pub mod memory;
+pub mod headless;
+pub mod interrupts;
+pub mod klaus;
+pub mod single_step;
+pub mod expression;
+pub mod storage;
+pub mod debugger;
+pub mod call_stack;
+pub mod cdl;
+pub mod profiler;
+pub mod hex_editor;
+pub mod ram_search;
+pub mod watch;
+pub mod controller;
+pub mod keymap;
 
-use cpu::CPU;
+use bus::Bus;
+use cpu::{Variant, CPU};
 use ui::ui;
 use cartridge::Cartridge;
 
 use std::env;
+use std::process;
+use std::str::FromStr;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let Some(command) = args.get(1) else { process::exit(usage()) };
 
-    let cartridge_path = &args[1];
-    let cpu = CPU::new(Cartridge::new(cartridge_path).unwrap());
+    // runes --nestest <nestest.nes> [nestest.log]
+    if command == "--nestest" {
+        let Some(rom) = args.get(2) else { process::exit(usage()) };
+        process::exit(nestest(rom, args.get(3)));
+    }
+
+    // runes --single-step <directory with 00.json..ff.json> [--cpu 2a03|6502|65c02]
+    if command == "--single-step" {
+        let Some(dir) = args.get(2) else { process::exit(usage()) };
+        let variant = option(&args, "--cpu").unwrap_or_default();
+        process::exit(single_step(variant, dir));
+    }
+
+    // runes <rom> [--ram-init zeros|ff|random|random:<seed>] [--cpu 2a03|6502|65c02] [--adapter none|four-score|famicom]
+    let cartridge_path = command;
+    let mut bus = Bus::new(Cartridge::new(cartridge_path).unwrap());
+    bus.ram_init = option(&args, "--ram-init").unwrap_or_default();
+    bus.controllers.adapter = option(&args, "--adapter").unwrap_or_default();
+
+    let mut cpu = CPU::with_memory(bus);
+    cpu.variant = option(&args, "--cpu").unwrap_or_default();
+    cpu.power_on();
     ui(cpu).unwrap();
 }
+
+fn usage() -> i32 {
+    eprintln!("usage: runes <rom.nes> [--ram-init zeros|ff|random|random:<seed>] [--cpu 2a03|6502|65c02] [--adapter none|four-score|famicom]");
+    eprintln!("       runes --nestest <nestest.nes> [nestest.log]");
+    eprintln!("       runes --single-step <directory> [--cpu 2a03|6502|65c02]");
+    1
+}
+
+// Parses the value following a command line option, exiting when it is missing or invalid
+fn option<T: FromStr<Err = String>>(args: &[String], name: &str) -> Option<T> {
+    let index = args.iter().position(|arg| arg == name)?;
+
+    match args.get(index + 1).map(|value| value.parse()) {
+        Some(Ok(value)) => Some(value),
+        Some(Err(error)) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+        None => {
+            eprintln!("{} needs a value", name);
+            process::exit(1);
+        },
+    }
+}
+
+fn nestest(rom: &str, reference: Option<&String>) -> i32 {
+    let result = match headless::run_nestest(rom) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    for line in &result.trace {
+        println!("{}", line);
+    }
+
+    if let Some(reference) = reference {
+        let reference = match std::fs::read_to_string(reference) {
+            Ok(reference) => reference,
+            Err(error) => {
+                eprintln!("{}: {}", reference, error);
+                return 1;
+            }
+        };
+        if let Err(divergence) = headless::compare_trace(&result.trace, &reference) {
+            eprintln!("{}", divergence);
+            return 1;
+        }
+    }
+
+    eprintln!("Official result: {:02X}, unofficial result: {:02X}", result.official_result, result.unofficial_result);
+
+    if result.official_result == 0 && result.unofficial_result == 0 { 0 } else { 1 }
+}
+
+fn single_step(variant: Variant, dir: &str) -> i32 {
+    let reports = match single_step::run_directory(variant, std::path::Path::new(dir)) {
+        Ok(reports) => reports,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    for report in &reports {
+        println!("{}", report);
+    }
+
+    let failed = reports.iter().filter(|report| !report.passed()).count();
+    eprintln!("{} opcodes tested, {} failed", reports.len(), failed);
+
+    if failed == 0 { 0 } else { 1 }
+}
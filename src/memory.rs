@@ -0,0 +1,126 @@
+use crate::bus::Bus;
+use crate::debugger::AccessKind;
+
+// Everything the CPU needs from the machine it is plugged into.
+// The NES Bus is one implementation, FlatMemory is a plain 64 KiB RAM for running generic 6502 code.
+pub trait Memory {
+    fn mem_read(&mut self, addr: u16) -> u8;
+
+    fn mem_write(&mut self, addr: u16, data: u8);
+
+    // Reads memory without any side effects, used by the debugger and trace logger
+    fn mem_peek(&self, addr: u16) -> u8;
+
+    // Advances the devices sharing the master clock by one tick
+    fn tick(&mut self) {}
+
+    // Puts RAM and devices in their power-up state
+    fn power_on(&mut self) {}
+
+    // Passes the reset button on to the devices
+    fn reset(&mut self) {}
+
+    // Returns true, and acknowledges it, when a device raised a non-maskable interrupt
+    fn poll_nmi(&mut self) -> bool {
+        false
+    }
+
+    // Level of the IRQ line, asserted while any device holds it
+    fn irq(&self) -> bool {
+        false
+    }
+
+    // Tells the code/data logger what the CPU used the byte at addr for (cdl flags)
+    fn log_code_data(&mut self, _addr: u16, _flags: u8) {}
+
+    // Returns the page of a sprite DMA the last write started, and acknowledges it
+    fn take_oam_dma(&mut self) -> Option<u8> {
+        None
+    }
+}
+
+impl Memory for Bus {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        let data = Bus::mem_read(self, addr);
+        self.access_log.record(AccessKind::Read, addr, data);
+        data
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.access_log.record(AccessKind::Write, addr, data);
+        Bus::mem_write(self, addr, data)
+    }
+
+    fn mem_peek(&self, addr: u16) -> u8 {
+        Bus::mem_peek(self, addr)
+    }
+
+    fn log_code_data(&mut self, addr: u16, flags: u8) {
+        Bus::log_code_data(self, addr, flags)
+    }
+
+    fn take_oam_dma(&mut self) -> Option<u8> {
+        self.oam_dma.take()
+    }
+
+    fn power_on(&mut self) {
+        Bus::power_on(self)
+    }
+
+    fn reset(&mut self) {
+        Bus::reset(self)
+    }
+
+    fn tick(&mut self) {
+        self.ppu.clock();
+        self.interrupts.set_nmi(self.ppu.nmi_line());
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        self.interrupts.poll_nmi()
+    }
+
+    fn irq(&self) -> bool {
+        self.interrupts.irq()
+    }
+}
+
+// Flat, fully writable 64 KiB address space with no devices attached
+pub struct FlatMemory {
+    pub data: Vec<u8>,
+}
+
+impl Default for FlatMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatMemory {
+    pub fn new() -> Self {
+        FlatMemory {
+            data: vec![0; 0x10000],
+        }
+    }
+
+    // Copies bytes into memory starting at addr, wrapping around at $FFFF
+    pub fn load(&mut self, addr: u16, bytes: &[u8]) {
+        for (i, byte) in bytes.iter().enumerate() {
+            self.data[addr.wrapping_add(i as u16) as usize] = *byte;
+        }
+    }
+}
+
+impl Memory for FlatMemory {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.data[addr as usize]
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.data[addr as usize] = data;
+    }
+
+    fn mem_peek(&self, addr: u16) -> u8 {
+        self.data[addr as usize]
+    }
+}
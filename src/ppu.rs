@@ -1,10 +1,27 @@
 use crate::cartridge::Mirroring;
+use crate::cdl;
+use crate::debugger::{AccessKind, AccessLog};
+
 pub enum PPUStatusFlags {
     SpriteOverflow = (1 << 5),
     SpriteZeroHit = (1 << 6),
     VerticalBlank = (1 << 7),
 }
 
+// A sprite picked for the current scanline, copied out of OAM so later OAM or PPUCTRL writes don't affect it
+#[derive(Clone, Copy, Debug)]
+struct LineSprite {
+    oam: [u8; 4], // Y, tile, attributes and X
+    height: u16,
+    zero: bool, // Sprite 0, for the sprite 0 hit
+}
+
+pub const WIDTH: usize = 256;
+pub const HEIGHT: usize = 240;
+
+// Bits of the I/O latch fade to 0 around 600 ms after they were last driven
+pub const IO_LATCH_DECAY_FRAMES: u64 = 36;
+
 pub enum PPUControlFlags {
     NametableX = (1 << 0),
     NametableY = (1 << 1),
@@ -45,12 +62,19 @@ pub struct PPU {
     address_latch: bool,
 
     pub control_register: u8,
-    pub nmi: bool,
 
     pub mask_register: u8,
 
     pub status_register: u8,
 
+    pub oam_addr: u8,
+
+    pub scroll_x: u8,
+    pub scroll_y: u8,
+
+    // The PPU's own data bus, what reads of the write-only registers return
+    io_latch: u8,
+    io_latch_frames: [u64; 8], // Frame each bit was last driven
 
     // Data Buffer
 
@@ -58,15 +82,23 @@ pub struct PPU {
 
     pub mirroring: Mirroring,
 
+    // Output of the visible dots, an index into SYSTEM_PALLETE per pixel
+    pub frame_buffer: Vec<u8>,
+    line_sprites: Vec<LineSprite>, // Secondary OAM, the sprites of the current scanline, at most 8
+
     // Miscs
     pub scanline: u16,
     pub cycle: u16,
+    pub frame: u64, // Frames started since power-up
 
+    pub access_log: AccessLog, // PPU address space accesses through $2007, recorded for watchpoints
+    pub chr_log: Vec<u8>, // Code/data logger flags of every CHR ROM byte
 }
 
 impl PPU {
     pub fn new(chr_rom: Vec<u8>, mirroring: Mirroring) -> PPU {
         PPU {
+            chr_log: vec![0; chr_rom.len()],
             chr_rom,
             vram: [0; 2048],
             oam: [0; 256],
@@ -75,25 +107,101 @@ impl PPU {
             address_latch: true,
 
             control_register: 0b0000_0000,
-            nmi: false,
 
             mask_register: 0b0000_0000,
 
             status_register: 0b0000_0000,
 
+            oam_addr: 0,
+
+            scroll_x: 0,
+            scroll_y: 0,
+
+            io_latch: 0,
+            io_latch_frames: [0; 8],
+
             data_buffer: 0b0000_0000,
 
             mirroring,
 
+            frame_buffer: vec![0; WIDTH * HEIGHT],
+            line_sprites: Vec::new(),
+
             scanline: 0,
             cycle: 0,
+            frame: 0,
+
+            access_log: AccessLog::default(),
+        }
+    }
+
+    // Registers at power-up, the frame starts at the top
+    pub fn power_on(&mut self) {
+        self.reset();
+        self.address_register = 0;
+        self.status_register = 0;
+        self.oam_addr = 0;
+        self.io_latch = 0;
+        self.scanline = 0;
+        self.cycle = 0;
+        self.frame = 0;
+    }
+
+    // The reset line clears the control and mask registers, the write latch and the read buffer
+    pub fn reset(&mut self) {
+        self.control_register = 0;
+        self.mask_register = 0;
+        self.scroll_x = 0;
+        self.scroll_y = 0;
+        self.address_latch = true;
+        self.data_buffer = 0;
+    }
+
+    // Registers at $2000-$2007, mirrored up to $3FFF
+    pub fn read_register(&mut self, addr: u16) -> u8 {
+        match addr & 0x0007 {
+            0x0002 => self.read_status_register(),
+            0x0004 => self.read_oam_data(),
+            0x0007 => self.read_data(),
+            // Write-only
+            _ => self.io_latch(),
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, data: u8) {
+        // Every write drives the latch, even to the read-only status register
+        self.drive_io_latch(data, 0xFF);
+
+        match addr & 0x0007 {
+            0x0000 => self.write_to_control_register(data),
+            0x0001 => self.write_to_mask_register(data),
+            0x0002 => {},
+            0x0003 => self.oam_addr = data,
+            0x0004 => self.write_oam_data(data),
+            0x0005 => self.write_to_scroll_register(data),
+            0x0006 => self.write_to_address_register(data),
+            _ => self.write_data(data),
+        }
+    }
+
+    // I/O latch as a read sees it, without the bits that decayed
+    pub fn io_latch(&self) -> u8 {
+        (0..8).filter(|bit| self.frame.saturating_sub(self.io_latch_frames[*bit]) < IO_LATCH_DECAY_FRAMES)
+            .fold(0, |latch, bit| latch | (self.io_latch & (1 << bit)))
+    }
+
+    // Sets the bits of mask to value, reads only drive the bits the register has
+    fn drive_io_latch(&mut self, value: u8, mask: u8) {
+        self.io_latch = (self.io_latch() & !mask) | (value & mask);
+        for bit in (0..8).filter(|bit| mask & (1 << bit) != 0) {
+            self.io_latch_frames[bit] = self.frame;
         }
     }
 
     // Mirroring
     pub fn mirror_vram_addr(&mut self, addr: u16) -> u16 {
-        let mirrored_vram = addr & 0b1011_1111_1111_1111;
-        let vram_index = mirrored_vram & 0x2000;
+        let mirrored_vram = addr & 0b1110_1111_1111_1111; // $3000-$3EFF mirrors $2000-$2EFF
+        let vram_index = mirrored_vram - 0x2000;
         let name_table = vram_index / 0x0400;
 
         match (&self.mirroring, name_table) {
@@ -146,19 +254,45 @@ impl PPU {
     }
 
     pub fn increment_vram_addr(&mut self) {
-        let increment: u8;
-        if self.control_register & 0b0000_0100 == 0 {
-            increment = 1
+        let increment: u8 = if self.control_register & 0b0000_0100 == 0 {
+            1
         } else {
-            increment = 32
-        }
+            32
+        };
 
         self.increment_address_register(increment);
     }
 
+    // Scroll Register
+
+    pub fn write_to_scroll_register(&mut self, data: u8) {
+        if self.address_latch {
+            self.scroll_x = data;
+        } else {
+            self.scroll_y = data;
+        }
+
+        self.address_latch = !self.address_latch;
+    }
+
+    // OAM
+
+    pub fn read_oam_data(&mut self) -> u8 {
+        let data = self.oam[self.oam_addr as usize];
+        self.drive_io_latch(data, 0xFF);
+        data
+    }
+
+    pub fn write_oam_data(&mut self, data: u8) {
+        self.oam[self.oam_addr as usize] = data;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
+    }
+
     // Status register
     pub fn read_status_register(&mut self) -> u8 {
-        let status = (self.status_register & 0xE0) | (self.data_buffer & 0x1F); // Noise
+        // The low bits aren't driven, they come from the latch
+        let status = (self.status_register & 0xE0) | (self.io_latch() & 0x1F);
+        self.drive_io_latch(status, 0xE0);
         self.set_status_flag(PPUStatusFlags::VerticalBlank, false);
         self.address_latch = true;
         status
@@ -173,71 +307,108 @@ impl PPU {
     }
 
 
+    // The NMI output is asserted while in vertical blank with NMI enabled, enabling it during vblank raises another one
+    pub fn nmi_line(&self) -> bool {
+        self.status_register & (PPUStatusFlags::VerticalBlank as u8) != 0
+            && self.control_register & (PPUControlFlags::EnableNMI as u8) != 0
+    }
+
     // Mask Register
     pub fn write_to_mask_register(&mut self, data: u8) {
         self.mask_register = data;
     }
     
 
+    // Register value as the CPU would read it, without clearing flags or moving the address
+    pub fn peek_register(&self, addr: u16) -> u8 {
+        match addr & 0x0007 {
+            0x0002 => (self.status_register & 0xE0) | (self.io_latch() & 0x1F),
+            0x0004 => self.oam[self.oam_addr as usize],
+            0x0007 => self.data_buffer,
+            _ => self.io_latch(),
+        }
+    }
+
     // PPU Read & Write
     pub fn read_data(&mut self) -> u8 {
-        self.increment_vram_addr();
-
-        match self.address_register {
+        let addr = self.address_register;
+        let data = match addr {
             0..=0x1FFF => {
-                // Read from CHR ROM
+                // Read from CHR ROM, without one the cartridge has CHR RAM that isn't emulated yet and the latch shows through
                 let data = self.data_buffer;
-                self.data_buffer = self.chr_rom[self.address_register as usize];
+                self.data_buffer = match self.chr_rom.get(addr as usize) {
+                    Some(&byte) => {
+                        self.chr_log[addr as usize] |= cdl::CHR_READ;
+                        byte
+                    },
+                    None => self.io_latch(),
+                };
                 data
-            }, 
-            0x2000..=0x2FFF => {
+            },
+            0x2000..=0x3EFF => {
                 // Read from VRAM
                 let data = self.data_buffer;
                 self.data_buffer = self.vram[self.mirror_vram_addr(self.address_register) as usize];
                 data
             },
-            0x3000..=0x3EFF => panic!("PPU invalid read with address register: {:#X}", self.address_register),
 
-            0x3F00..=0x3FFF => {
-                self.palette[(self.address_register - 0x3f00) as usize]
-            },
+            // Palette entries are 6 bits, the top two come from the latch
+            _ => (self.palette[self.palette_index(addr)] & 0x3F) | (self.io_latch() & 0xC0),
+        };
 
-            _ => panic!("PPU invalid read with address register: {:#X}", self.address_register),
-        }
+        // The byte fetched from PPU memory, reads below the palettes only return it on the next read
+        let fetched = if addr < 0x3F00 { self.data_buffer } else { data };
+        self.access_log.record(AccessKind::PpuRead, addr, fetched);
+        self.drive_io_latch(data, if addr < 0x3F00 { 0xFF } else { 0x3F });
+        self.increment_vram_addr();
+        data
     }
 
     pub fn write_data(&mut self, data: u8) {
-        self.vram[self.address_register as usize] = data;
+        let addr = self.address_register;
+        self.access_log.record(AccessKind::PpuWrite, addr, data);
+
+        match addr {
+            // CHR ROM can't be written
+            0..=0x1FFF => {},
+            0x2000..=0x3EFF => {
+                let index = self.mirror_vram_addr(addr) as usize;
+                self.vram[index] = data;
+            },
+            _ => {
+                let index = self.palette_index(addr);
+                self.palette[index] = data;
+            },
+        }
+
         self.increment_vram_addr();
     }
 
+    // $3F00-$3FFF mirrors the 32 palette entries, the background color entries of the sprite palettes mirror the ones of the background
+    fn palette_index(&self, addr: u16) -> usize {
+        let index = (addr & 0x1F) as usize;
+        if index >= 0x10 && index.is_multiple_of(4) { index - 0x10 } else { index }
+    }
+
     pub fn clock(&mut self) {
         match self.scanline {
             0..=239 => {
-                match self.cycle {
-                    1..=256 => {
-                        // Background Rendering
-                    },
-                    257 => {
-                        // Sprite Evaluation
-                    },
-                    321..=336 => {
-                        // Background Rendering
-                    },
-                    _ => {},
+                if self.cycle == 1 {
+                    self.evaluate_sprites();
+                }
+                if (1..=256).contains(&self.cycle) {
+                    self.render_dot();
                 }
             },
             240 => {
                 // Post Render Scanline - Do Nothing
             },
-            241..=260 => {
-                if self.scanline == 241 && self.cycle == 1 {
-                    self.set_status_flag(PPUStatusFlags::VerticalBlank, true);
-
-                    if self.get_control_flag(PPUControlFlags::EnableNMI) {
-                        self.nmi = true;
-                    }
-                }
+            241 if self.cycle == 1 => {
+                self.set_status_flag(PPUStatusFlags::VerticalBlank, true);
+            },
+            261 if self.cycle == 1 => {
+                self.set_status_flag(PPUStatusFlags::SpriteZeroHit, false);
+                self.set_status_flag(PPUStatusFlags::SpriteOverflow, false);
             },
 
             _ => {},
@@ -251,9 +422,140 @@ impl PPU {
 
             if self.scanline > 261 {
                 self.scanline = 0;
+                self.frame += 1;
                 self.set_status_flag(PPUStatusFlags::VerticalBlank, false);
             }
         }
     }
 
+    // Rendering
+    // Every visible dot is worked out on its own from the registers as they are at that dot. There are no
+    // shift registers or scroll latches, so mid-scanline effects and split scrolling through $2006 aren't reproduced.
+
+    // Picks the sprites of the current scanline, the ones after the 8th set the overflow flag
+    fn evaluate_sprites(&mut self) {
+        let height = self.sprite_height();
+        self.line_sprites.clear();
+
+        for sprite in 0..64 {
+            // Sprites show up one scanline below their Y
+            let top = self.oam[sprite * 4] as u16 + 1;
+            if (top..top + height).contains(&self.scanline) {
+                if self.line_sprites.len() == 8 {
+                    self.set_status_flag(PPUStatusFlags::SpriteOverflow, true);
+                    break;
+                }
+                let oam = [0, 1, 2, 3].map(|byte| self.oam[sprite * 4 + byte]);
+                self.line_sprites.push(LineSprite { oam, height, zero: sprite == 0 });
+            }
+        }
+    }
+
+    fn sprite_height(&self) -> u16 {
+        if self.control_register & PPUControlFlags::SpriteSize as u8 != 0 { 16 } else { 8 }
+    }
+
+    fn render_dot(&mut self) {
+        let x = self.cycle - 1;
+        let y = self.scanline;
+
+        // The mask can hide either layer, or just its leftmost 8 pixels
+        let show_background = self.mask_register & 0x08 != 0 && (x >= 8 || self.mask_register & 0x02 != 0);
+        let show_sprites = self.mask_register & 0x10 != 0 && (x >= 8 || self.mask_register & 0x04 != 0);
+
+        let background = if show_background { self.background_pixel(x, y) } else { 0 };
+        let sprite = if show_sprites { self.sprite_pixel(x, y) } else { None };
+
+        // Palette entry, the low two bits are 0 for a transparent pixel
+        let entry = match sprite {
+            Some((entry, behind, zero)) => {
+                if zero && background & 0x03 != 0 && x != 255 {
+                    self.set_status_flag(PPUStatusFlags::SpriteZeroHit, true);
+                }
+                if behind && background & 0x03 != 0 { background } else { entry }
+            },
+            None => background,
+        };
+
+        let color = self.palette[self.palette_index(0x3F00 | entry as u16)] & 0x3F;
+        self.frame_buffer[y as usize * WIDTH + x as usize] = color;
+    }
+
+    // Palette entry (palette * 4 + pixel) of the background at dot x of scanline y, 0 where it is transparent
+    fn background_pixel(&mut self, x: u16, y: u16) -> u8 {
+        // Position in the 512x480 plane of the four nametables
+        let plane_x = (x + self.scroll_x as u16 + (self.control_register & 0x01) as u16 * 256) % 512;
+        let plane_y = (y + self.scroll_y as u16 + ((self.control_register >> 1) & 0x01) as u16 * 240) % 480;
+
+        let nametable = 0x2000 + (plane_y / 240 * 2 + plane_x / 256) * 0x400;
+        let (column, row) = ((plane_x % 256) / 8, (plane_y % 240) / 8);
+
+        let tile_addr = self.mirror_vram_addr(nametable + row * 32 + column);
+        let tile = self.vram[tile_addr as usize] as u16;
+
+        // A byte of attributes for every 4x4 tiles, two bits per 2x2
+        let attribute_addr = self.mirror_vram_addr(nametable + 0x3C0 + row / 4 * 8 + column / 4);
+        let shift = (row % 4 / 2) * 4 + (column % 4 / 2) * 2;
+        let palette = (self.vram[attribute_addr as usize] >> shift) & 0x03;
+
+        let table = if self.control_register & PPUControlFlags::PatternBackground as u8 != 0 { 0x1000 } else { 0 };
+        let pixel = self.pattern_pixel(table + tile * 16, plane_y % 8, plane_x % 8);
+
+        if pixel == 0 { 0 } else { palette * 4 + pixel }
+    }
+
+    // Palette entry of the first opaque sprite at dot x of scanline y, whether it is behind the background and is sprite 0
+    fn sprite_pixel(&mut self, x: u16, y: u16) -> Option<(u8, bool, bool)> {
+        for index in 0..self.line_sprites.len() {
+            let LineSprite { oam: [top, tile, attributes, left], height, zero } = self.line_sprites[index];
+
+            let column = x.wrapping_sub(left as u16);
+            if column >= 8 {
+                continue;
+            }
+
+            let mut row = y - (top as u16 + 1);
+            let column = if attributes & 0x40 != 0 { 7 - column } else { column };
+            if attributes & 0x80 != 0 {
+                row = height - 1 - row;
+            }
+
+            // 8x16 sprites pick their pattern table with bit 0 of the tile, the bottom half is the next tile
+            let addr = if height == 16 {
+                let table = (tile as u16 & 0x01) * 0x1000;
+                table + ((tile as u16 & 0xFE) + row / 8) * 16
+            } else {
+                let table = if self.control_register & PPUControlFlags::PatternSprite as u8 != 0 { 0x1000 } else { 0 };
+                table + tile as u16 * 16
+            };
+
+            let pixel = self.pattern_pixel(addr, row % 8, column);
+            if pixel != 0 {
+                return Some((0x10 + (attributes & 0x03) * 4 + pixel, attributes & 0x20 != 0, zero));
+            }
+        }
+
+        None
+    }
+
+    // Two bit pixel of the tile at addr in the pattern tables
+    fn pattern_pixel(&mut self, addr: u16, row: u16, column: u16) -> u8 {
+        let lo = self.read_pattern(addr + row);
+        let hi = self.read_pattern(addr + row + 8);
+        let bit = 7 - column;
+
+        ((hi >> bit) & 0x01) << 1 | ((lo >> bit) & 0x01)
+    }
+
+    fn read_pattern(&mut self, addr: u16) -> u8 {
+        match self.chr_rom.get(addr as usize) {
+            Some(&data) => {
+                self.chr_log[addr as usize] |= cdl::CHR_RENDERED;
+                data
+            },
+            // No CHR ROM, the cartridge has CHR RAM that isn't emulated yet
+            None => 0,
+        }
+    }
+
 }
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+use crate::call_stack::FrameKind;
+use crate::cpu::CPU;
+
+// CPU cycles in the 20 vblank scanlines, what an NMI handler has before rendering starts again
+pub const VBLANK_CYCLES: u64 = 20 * 341 / 3;
+// NMI timings kept for the Profiler tab
+const MAX_NMI_RUNS: usize = 600;
+
+// A subroutine or interrupt handler, by how it was entered and where
+pub type Routine = (FrameKind, u16);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AddressStats {
+    pub instructions: u64,
+    pub cycles: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoutineStats {
+    pub calls: u64,
+    pub exclusive: u64, // Cycles spent in the routine itself
+    pub inclusive: u64, // Cycles including the routines it called
+}
+
+// Time an NMI handler took, from the interrupt sequence to its RTI
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NmiRun {
+    pub frame: u64,
+    pub cycles: u64,
+    pub overrun: bool, // Still running after vblank ended, or when the next NMI came in
+}
+
+// Name of a routine in reports, without spaces so collapsed stacks stay readable by flamegraph tools
+pub fn routine_name((kind, addr): Routine) -> String {
+    match kind {
+        FrameKind::Jsr => format!("sub_{:04X}", addr),
+        FrameKind::Brk => format!("brk_{:04X}", addr),
+        FrameKind::Nmi => format!("nmi_{:04X}", addr),
+        FrameKind::Irq => format!("irq_{:04X}", addr),
+    }
+}
+
+// Counts the cycles of every instruction against its address and the routines on the shadow call stack
+pub struct Profiler {
+    pub enabled: bool,
+    pub cycles: u64, // Profiled so far
+    pub addresses: Vec<AddressStats>, // Indexed by address
+    pub routines: HashMap<Routine, RoutineStats>,
+    pub nmi_runs: VecDeque<NmiRun>,
+    stacks: HashMap<Vec<Routine>, u64>, // Exclusive cycles per call path, outermost first
+    path: Vec<Routine>, // Call stack before the next instruction
+    levels: Vec<u8>, // Stack pointer of every frame in path
+    nmi: Option<(u64, u8, u64)>, // Start cycle, stack pointer and frame of the NMI handler running
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler {
+            enabled: false,
+            cycles: 0,
+            addresses: vec![AddressStats::default(); 0x10000],
+            routines: HashMap::new(),
+            nmi_runs: VecDeque::new(),
+            stacks: HashMap::new(),
+            path: Vec::new(),
+            levels: Vec::new(),
+            nmi: None,
+        }
+    }
+}
+
+impl Profiler {
+    pub fn clear(&mut self) {
+        *self = Profiler { enabled: self.enabled, ..Profiler::default() };
+    }
+
+    // Counts the instruction at pc that just ran for cycles, interrupt sequence included
+    pub fn record(&mut self, cpu: &CPU, pc: u16, cycles: u64) {
+        self.cycles += cycles;
+
+        let address = &mut self.addresses[pc as usize];
+        address.instructions += 1;
+        address.cycles += cycles;
+
+        if let Some(&innermost) = self.path.last() {
+            self.routines.entry(innermost).or_default().exclusive += cycles;
+        }
+        // Recursive routines only count once per path
+        for (index, routine) in self.path.iter().enumerate() {
+            if !self.path[..index].contains(routine) {
+                self.routines.entry(*routine).or_default().inclusive += cycles;
+            }
+        }
+        match self.stacks.get_mut(&self.path) {
+            Some(total) => *total += cycles,
+            None => { self.stacks.insert(self.path.clone(), cycles); },
+        }
+
+        // Frames that differ from the previous stack were entered by this instruction or an interrupt after it
+        let frames = &cpu.call_stack.frames;
+        let unchanged = self.path.iter().zip(&self.levels).zip(frames)
+            .take_while(|((routine, level), frame)| **routine == (frame.kind, frame.target) && **level == frame.stack_pointer)
+            .count();
+
+        self.path.truncate(unchanged);
+        self.levels.truncate(unchanged);
+        for frame in &frames[unchanged..] {
+            self.routines.entry((frame.kind, frame.target)).or_default().calls += 1;
+            self.path.push((frame.kind, frame.target));
+            self.levels.push(frame.stack_pointer);
+        }
+
+        self.time_nmi(cpu);
+    }
+
+    fn time_nmi(&mut self, cpu: &CPU) {
+        let now = cpu.total_cycles();
+
+        if let Some((start, stack_pointer, frame)) = self.nmi {
+            let running = self.path.iter().zip(&self.levels).any(|(&(kind, _), &level)| kind == FrameKind::Nmi && level == stack_pointer);
+            let interrupted = cpu.interrupted == Some(0xFFFA);
+
+            if !running || interrupted {
+                let cycles = now - start;
+                self.nmi = None;
+                self.push_nmi_run(NmiRun { frame, cycles, overrun: interrupted || cycles > VBLANK_CYCLES });
+            }
+        }
+
+        // The interrupt sequence took the last 7 cycles
+        if cpu.interrupted == Some(0xFFFA) {
+            self.nmi = Some((now - 7, cpu.stack_pointer, cpu.bus.ppu.frame));
+        }
+    }
+
+    fn push_nmi_run(&mut self, run: NmiRun) {
+        if self.nmi_runs.len() == MAX_NMI_RUNS {
+            self.nmi_runs.pop_front();
+        }
+        self.nmi_runs.push_back(run);
+    }
+
+    // Per address hot spots: address,instructions,cycles,percent
+    pub fn addresses_csv(&self) -> String {
+        let mut csv = "address,instructions,cycles,percent\n".to_string();
+        for (addr, stats) in self.addresses.iter().enumerate().filter(|(_, stats)| stats.instructions > 0) {
+            writeln!(csv, "${:04X},{},{},{:.3}", addr, stats.instructions, stats.cycles, self.percent(stats.cycles)).unwrap();
+        }
+        csv
+    }
+
+    // Per routine totals: routine,address,kind,calls,exclusive_cycles,inclusive_cycles
+    pub fn routines_csv(&self) -> String {
+        let mut routines: Vec<_> = self.routines.iter().collect();
+        routines.sort_by_key(|(routine, _)| (routine.1, routine.0 as u8));
+
+        let mut csv = "routine,address,kind,calls,exclusive_cycles,inclusive_cycles\n".to_string();
+        for (&routine, stats) in routines {
+            writeln!(csv, "{},${:04X},{},{},{},{}", routine_name(routine), routine.1, routine.0, stats.calls, stats.exclusive, stats.inclusive).unwrap();
+        }
+        csv
+    }
+
+    // Brendan Gregg's collapsed stack format, one "outer;inner cycles" line per call path.
+    // Code outside any routine is under "main".
+    pub fn collapsed_stacks(&self) -> String {
+        let mut lines: Vec<String> = self.stacks.iter().map(|(path, cycles)| {
+            let names: Vec<String> = std::iter::once("main".to_string()).chain(path.iter().map(|&routine| routine_name(routine))).collect();
+            format!("{} {}", names.join(";"), cycles)
+        }).collect();
+
+        lines.sort();
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    pub fn percent(&self, cycles: u64) -> f64 {
+        if self.cycles == 0 { 0.0 } else { cycles as f64 * 100.0 / self.cycles as f64 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::cartridge;
+    use crate::debugger::{Command, Debugger};
+
+    // Runs the program to its first BRK with the profiler on
+    fn profile(cpu: &mut CPU) -> Debugger {
+        let mut debugger = Debugger::new();
+        debugger.profiler.enabled = true;
+
+        while cpu.peek(cpu.program_counter) != 0x00 {
+            debugger.step(cpu);
+        }
+        debugger
+    }
+
+    #[test]
+    fn cycles_per_address_and_routine() {
+        let mut cpu = CPU::new(cartridge("
+                JSR outer
+                JSR inner
+                BRK
+            outer:
+                JSR inner
+                NOP
+                RTS
+            inner:
+                NOP
+                RTS
+            "));
+        let profiler = profile(&mut cpu).profiler;
+
+        // JSR 6 + (JSR 6 + NOP 2 + RTS 6 + NOP 2 + RTS 6) + JSR 6 + NOP 2 + RTS 6
+        assert_eq!(profiler.cycles, 42);
+        assert_eq!(profiler.addresses[0x8000], AddressStats { instructions: 1, cycles: 6 });
+        assert_eq!(profiler.addresses[0x800C], AddressStats { instructions: 2, cycles: 4 });
+
+        let outer = profiler.routines[&(FrameKind::Jsr, 0x8007)];
+        let inner = profiler.routines[&(FrameKind::Jsr, 0x800C)];
+        assert_eq!(outer, RoutineStats { calls: 1, exclusive: 14, inclusive: 22 });
+        assert_eq!(inner, RoutineStats { calls: 2, exclusive: 16, inclusive: 16 });
+
+        assert_eq!(profiler.collapsed_stacks(), "main 12\nmain;sub_8007 14\nmain;sub_8007;sub_800C 8\nmain;sub_800C 8\n");
+        assert_eq!(profiler.routines_csv().lines().nth(2), Some("sub_800C,$800C,JSR,2,16,16"));
+        assert_eq!(profiler.addresses_csv().lines().nth(1), Some("$8000,1,6,14.286"));
+    }
+
+    #[test]
+    fn nmi_handler_time() {
+        let mut cpu = CPU::new(cartridge("
+            loop:
+                JMP loop
+            "));
+        // LDX #$00, DEX, BNE back to DEX, RTI: a handler that runs for 2 + 256 * 5 - 1 + 6 cycles
+        cpu.bus.cartridge.prg_rom[0x7FF0..0x7FF6].copy_from_slice(&[0xA2, 0x00, 0xCA, 0xD0, 0xFD, 0x40]);
+
+        let mut debugger = Debugger::new();
+        debugger.profiler.enabled = true;
+
+        cpu.bus.interrupts.set_nmi(true);
+        debugger.start(&cpu, Command::RunTo(0x8000));
+        debugger.run(&mut cpu, 10_000);
+
+        let run = debugger.profiler.nmi_runs[0];
+        assert_eq!(run.cycles, 7 + 2 + 256 * 5 - 1 + 6);
+        assert!(!run.overrun);
+        assert_eq!(debugger.profiler.routines[&(FrameKind::Nmi, 0xFFF0)].calls, 1);
+    }
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+
+use crate::bus::Bus;
+
+// Where game state lives: internal RAM and the cartridge's PRG RAM
+pub const REGIONS: [(u16, u16); 2] = [(0x0000, 0x07FF), (0x6000, 0x7FFF)];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    Byte,
+    Word, // Little-endian
+}
+
+// How the bytes at an address are read as a number
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub size: Size,
+    pub signed: bool,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View { size: Size::Byte, signed: false }
+    }
+}
+
+impl View {
+    pub fn raw<F: Fn(u16) -> u8>(&self, peek: F, addr: u16) -> u16 {
+        match self.size {
+            Size::Byte => peek(addr) as u16,
+            Size::Word => u16::from_le_bytes([peek(addr), peek(addr.wrapping_add(1))]),
+        }
+    }
+
+    pub fn value(&self, raw: u16) -> i64 {
+        match (self.size, self.signed) {
+            (Size::Byte, false) => raw as u8 as i64,
+            (Size::Byte, true) => raw as u8 as i8 as i64,
+            (Size::Word, false) => raw as i64,
+            (Size::Word, true) => raw as i16 as i64,
+        }
+    }
+
+    // Raw bytes of value, wrapped around to the view's size like the counters in the game do
+    pub fn wrap(&self, value: i64) -> u16 {
+        match self.size {
+            Size::Byte => value as u8 as u16,
+            Size::Word => value as u16,
+        }
+    }
+
+    pub fn hex(&self, raw: u16) -> String {
+        match self.size {
+            Size::Byte => format!("${:02X}", raw),
+            Size::Word => format!("${:04X}", raw),
+        }
+    }
+
+    // Parses a value typed in for the view, decimal or $hex
+    pub fn parse(&self, text: &str) -> Result<u16, String> {
+        let text = text.trim();
+        let value = match text.strip_prefix('$') {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => text.parse(),
+        }.map_err(|_| format!("invalid value '{}'", text))?;
+
+        let (min, max) = match (self.size, self.signed) {
+            (Size::Byte, false) => (0, 0xFF),
+            (Size::Byte, true) => (-0x80, 0xFF),
+            (Size::Word, false) => (0, 0xFFFF),
+            (Size::Word, true) => (-0x8000, 0xFFFF),
+        };
+        if value < min || value > max {
+            return Err(format!("{} doesn't fit in the view", value));
+        }
+
+        Ok(value as u16 & if self.size == Size::Byte { 0xFF } else { 0xFFFF })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+}
+
+pub const OPERATORS: [Operator; 4] = [Operator::Equal, Operator::NotEqual, Operator::Greater, Operator::Less];
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operator::Equal => write!(f, "="),
+            Operator::NotEqual => write!(f, "≠"),
+            Operator::Greater => write!(f, ">"),
+            Operator::Less => write!(f, "<"),
+        }
+    }
+}
+
+// What the current value is compared against
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Previous,
+    Value(i64),
+    ChangedBy(i64), // The previous value plus N, "= changed by -1" keeps a lives counter that just went down
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Filter {
+    pub operator: Operator,
+    pub operand: Operand,
+}
+
+impl Filter {
+    fn keeps(&self, view: View, current: u16, previous: u16) -> bool {
+        let current = view.value(current);
+        let previous = view.value(previous);
+        let other = match self.operand {
+            Operand::Previous => previous,
+            Operand::Value(value) => value,
+            // $00 changed by -1 is $FF
+            Operand::ChangedBy(delta) => view.value(view.wrap(previous + delta)),
+        };
+
+        match self.operator {
+            Operator::Equal => current == other,
+            Operator::NotEqual => current != other,
+            Operator::Greater => current > other,
+            Operator::Less => current < other,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub addr: u16,
+    pub previous: u16, // Raw value when the last snapshot or filter ran
+}
+
+// A value kept in memory, written back every frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cheat {
+    pub addr: u16,
+    pub size: Size,
+    pub value: u16,
+    pub enabled: bool,
+}
+
+impl Cheat {
+    pub fn apply(&self, bus: &mut Bus) {
+        if !self.enabled {
+            return;
+        }
+
+        // Straight to memory, without tripping write breakpoints
+        let [lo, hi] = self.value.to_le_bytes();
+        bus.mem_write(self.addr, lo);
+        if self.size == Size::Word {
+            bus.mem_write(self.addr.wrapping_add(1), hi);
+        }
+    }
+}
+
+// Narrows the addresses of RAM down to the ones that hold a value, one filter at a time
+#[derive(Default)]
+pub struct RamSearch {
+    pub view: View,
+    pub candidates: Vec<Candidate>,
+    pub filters: usize, // Applied since the snapshot
+    pub cheats: Vec<Cheat>,
+}
+
+impl RamSearch {
+    // Starts over with every address as a candidate
+    pub fn snapshot<F: Fn(u16) -> u8>(&mut self, peek: F) {
+        // A word can't straddle the end of a region
+        let last = if self.view.size == Size::Word { 1 } else { 0 };
+
+        self.candidates = REGIONS.iter()
+            .flat_map(|&(start, end)| start..=end - last)
+            .map(|addr| Candidate { addr, previous: self.view.raw(&peek, addr) })
+            .collect();
+        self.filters = 0;
+    }
+
+    // Keeps the candidates that pass, their current value becomes the previous one for the next filter
+    pub fn filter<F: Fn(u16) -> u8>(&mut self, peek: F, filter: Filter) {
+        let view = self.view;
+
+        self.candidates.retain_mut(|candidate| {
+            let current = view.raw(&peek, candidate.addr);
+            let keep = filter.keeps(view, current, candidate.previous);
+            candidate.previous = current;
+            keep
+        });
+        self.filters += 1;
+    }
+
+    // Freezes addr at its current value
+    pub fn freeze<F: Fn(u16) -> u8>(&mut self, peek: F, addr: u16) {
+        let cheat = Cheat { addr, size: self.view.size, value: self.view.raw(peek, addr), enabled: true };
+
+        match self.cheats.iter_mut().find(|other| other.addr == addr) {
+            Some(other) => *other = cheat,
+            None => self.cheats.push(cheat),
+        }
+    }
+
+    pub fn apply_cheats(&self, bus: &mut Bus) {
+        for cheat in &self.cheats {
+            cheat.apply(bus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::cartridge;
+
+    #[test]
+    fn filters_narrow_down_candidates() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        let mut search = RamSearch::default();
+
+        bus.cpu_vram[0x0042] = 3;
+        bus.cartridge.prg_ram[0x0010] = 3;
+        search.snapshot(|addr| bus.mem_peek(addr));
+        assert_eq!(search.candidates.len(), 0x0800 + 0x2000);
+
+        // A lives counter going from 3 to 2, and some other byte that went up
+        bus.cpu_vram[0x0042] = 2;
+        bus.cpu_vram[0x0100] = 9;
+        search.filter(|addr| bus.mem_peek(addr), Filter { operator: Operator::NotEqual, operand: Operand::Previous });
+        assert_eq!(search.candidates.len(), 2);
+
+        search.filter(|addr| bus.mem_peek(addr), Filter { operator: Operator::Equal, operand: Operand::Value(2) });
+        assert_eq!(search.candidates, vec![Candidate { addr: 0x0042, previous: 2 }]);
+
+        search.snapshot(|addr| bus.mem_peek(addr));
+        bus.cpu_vram[0x0042] = 1;
+        bus.cartridge.prg_ram[0x0010] = 4;
+        search.filter(|addr| bus.mem_peek(addr), Filter { operator: Operator::Equal, operand: Operand::ChangedBy(-1) });
+        assert_eq!(search.candidates.iter().map(|c| c.addr).collect::<Vec<_>>(), vec![0x0042]);
+        assert_eq!(search.filters, 1);
+    }
+
+    #[test]
+    fn changes_wrap_around() {
+        let mut memory = [0x00, 0x7F, 0x00, 0x00];
+        let changed_by = |delta| Filter { operator: Operator::Equal, operand: Operand::ChangedBy(delta) };
+
+        // An unsigned byte going from $00 to $FF, a signed byte from 127 to -128
+        let mut search = RamSearch::default();
+        search.snapshot(|addr| memory.get(addr as usize).copied().unwrap_or(0));
+        memory[0] = 0xFF;
+        search.filter(|addr| memory.get(addr as usize).copied().unwrap_or(0), changed_by(-1));
+        assert_eq!(search.candidates.iter().map(|c| c.addr).collect::<Vec<_>>(), vec![0x0000]);
+
+        search.view = View { size: Size::Byte, signed: true };
+        search.snapshot(|addr| memory.get(addr as usize).copied().unwrap_or(0));
+        memory[1] = 0x80;
+        search.filter(|addr| memory.get(addr as usize).copied().unwrap_or(0), changed_by(1));
+        assert_eq!(search.candidates.iter().map(|c| c.addr).collect::<Vec<_>>(), vec![0x0001]);
+
+        // A word going from $0000 to $FFFF
+        search.view = View { size: Size::Word, signed: false };
+        search.snapshot(|addr| memory.get(addr as usize).copied().unwrap_or(0));
+        memory[2..4].copy_from_slice(&[0xFF, 0xFF]);
+        search.filter(|addr| memory.get(addr as usize).copied().unwrap_or(0), changed_by(-1));
+        assert_eq!(search.candidates.iter().map(|c| c.addr).collect::<Vec<_>>(), vec![0x0002]);
+    }
+
+    #[test]
+    fn signed_and_word_views() {
+        let memory = |addr: u16| match addr {
+            0x0010 => 0xFF,
+            0x0011 => 0x01,
+            _ => 0x00,
+        };
+
+        let byte = View { size: Size::Byte, signed: true };
+        assert_eq!(byte.value(byte.raw(memory, 0x0010)), -1);
+        assert_eq!(byte.parse("-1"), Ok(0xFF));
+        assert!(byte.parse("256").is_err());
+
+        let word = View { size: Size::Word, signed: false };
+        assert_eq!(word.value(word.raw(memory, 0x0010)), 0x01FF);
+        assert_eq!(word.parse("$1FF"), Ok(0x01FF));
+
+        // Signed, $FF is less than 0
+        let mut search = RamSearch { view: byte, ..RamSearch::default() };
+        search.snapshot(memory);
+        search.filter(memory, Filter { operator: Operator::Less, operand: Operand::Value(0) });
+        assert_eq!(search.candidates.iter().map(|c| c.addr).collect::<Vec<_>>(), vec![0x0010]);
+
+        // Words stop one byte before the end of each region
+        search.view = word;
+        search.snapshot(memory);
+        assert_eq!(search.candidates.len(), 0x07FF + 0x1FFF);
+        assert_eq!(search.candidates.last().map(|c| c.addr), Some(0x7FFE));
+    }
+
+    #[test]
+    fn frozen_values() {
+        let mut bus = Bus::new(cartridge("NOP"));
+        let mut search = RamSearch { view: View { size: Size::Word, signed: false }, ..RamSearch::default() };
+
+        bus.cpu_vram[0x0300] = 0x34;
+        bus.cpu_vram[0x0301] = 0x12;
+        search.freeze(|addr| bus.mem_peek(addr), 0x0300);
+
+        bus.cpu_vram[0x0300] = 0x00;
+        bus.cpu_vram[0x0301] = 0x00;
+        search.apply_cheats(&mut bus);
+        assert_eq!(&bus.cpu_vram[0x0300..0x0302], &[0x34, 0x12]);
+
+        search.cheats[0].enabled = false;
+        bus.cpu_vram[0x0300] = 0x00;
+        search.apply_cheats(&mut bus);
+        assert_eq!(bus.cpu_vram[0x0300], 0x00);
+    }
+}
@@ -7,6 +7,12 @@ pub struct PPURenderer {
     pub pixels: Vec<u8>,
 }
 
+impl Default for PPURenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PPURenderer {
     pub fn new() -> Self {
         Self {
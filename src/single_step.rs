@@ -0,0 +1,329 @@
+use std::fmt;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::cpu::{Variant, CPU};
+use crate::memory::{FlatMemory, Memory};
+
+// Runs the per-instruction vectors from SingleStepTests (Tom Harte's ProcessorTests), nes6502 flavour
+// for the 2A03, or the 65x02 sets for the other variants.
+// There is one file per opcode, named after it in lower case hex (a9.json), holding an array of tests:
+// { "name", "initial": { "pc", "s", "a", "x", "y", "p", "ram": [[addr, value]] }, "final": { ... },
+//   "cycles": [[addr, value, "read" | "write"]] }
+
+// Keeps the report readable when a whole opcode is broken
+const MAX_REPORTED_MISMATCHES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+// Flat memory that records every bus access the CPU makes
+pub struct RecordingMemory {
+    pub memory: FlatMemory,
+    pub accesses: Vec<(u16, u8, Access)>,
+}
+
+impl RecordingMemory {
+    pub fn new() -> Self {
+        RecordingMemory {
+            memory: FlatMemory::new(),
+            accesses: Vec::new(),
+        }
+    }
+}
+
+impl Default for RecordingMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Memory for RecordingMemory {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        let data = self.memory.mem_read(addr);
+        self.accesses.push((addr, data, Access::Read));
+        data
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.accesses.push((addr, data, Access::Write));
+        self.memory.mem_write(addr, data);
+    }
+
+    fn mem_peek(&self, addr: u16) -> u8 {
+        self.memory.mem_peek(addr)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    PC,
+    S,
+    A,
+    X,
+    Y,
+    P,
+    Ram(u16),
+    Cycles,
+    Bus(usize),
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Field::PC => write!(f, "pc"),
+            Field::S => write!(f, "s"),
+            Field::A => write!(f, "a"),
+            Field::X => write!(f, "x"),
+            Field::Y => write!(f, "y"),
+            Field::P => write!(f, "p"),
+            Field::Ram(addr) => write!(f, "ram[${:04X}]", addr),
+            Field::Cycles => write!(f, "cycles"),
+            Field::Bus(cycle) => write!(f, "bus cycle {}", cycle),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Mismatch {
+    pub test: String,
+    pub field: Field,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\" {}: expected {}, got {}", self.test, self.field, self.expected, self.actual)
+    }
+}
+
+pub struct OpcodeReport {
+    pub variant: Variant,
+    pub opcode: u8,
+    pub tests: usize,
+    pub failed: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl OpcodeReport {
+    pub fn passed(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+impl fmt::Display for OpcodeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let instruction = &self.variant.instructions()[self.opcode as usize];
+        write!(
+            f,
+            "${:02X} {:?} {:?}: {}/{} passed",
+            self.opcode, instruction.operate, instruction.addrmode, self.tests - self.failed, self.tests
+        )?;
+
+        for mismatch in &self.mismatches {
+            write!(f, "\n    {}", mismatch)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Runs the files of all 256 opcodes in the directory, a partial set is an error rather than a partial pass
+pub fn run_directory(variant: Variant, dir: &Path) -> Result<Vec<OpcodeReport>, String> {
+    let missing: Vec<String> = (0..=255u8)
+        .map(|opcode| format!("{:02x}.json", opcode))
+        .filter(|file| !dir.join(file).exists())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("{}: missing {} opcode files ({})", dir.display(), missing.len(), missing.join(", ")));
+    }
+
+    let mut reports = Vec::new();
+    for opcode in 0..=255u8 {
+        let path = dir.join(format!("{:02x}.json", opcode));
+        let json = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        reports.push(run_opcode(variant, opcode, &json).map_err(|e| format!("{}: {}", path.display(), e))?);
+    }
+
+    Ok(reports)
+}
+
+pub fn run_opcode(variant: Variant, opcode: u8, json: &str) -> Result<OpcodeReport, String> {
+    let tests: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let tests = tests.as_array().ok_or("expected an array of tests")?;
+
+    let mut report = OpcodeReport {
+        variant,
+        opcode,
+        tests: tests.len(),
+        failed: 0,
+        mismatches: Vec::new(),
+    };
+
+    for test in tests {
+        let mismatches = run_test(variant, test)?;
+        if !mismatches.is_empty() {
+            report.failed += 1;
+            let room = MAX_REPORTED_MISMATCHES.saturating_sub(report.mismatches.len());
+            report.mismatches.extend(mismatches.into_iter().take(room));
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn run_test(variant: Variant, test: &Value) -> Result<Vec<Mismatch>, String> {
+    let name = test["name"].as_str().unwrap_or("").to_string();
+    let initial = &test["initial"];
+    let expected = &test["final"];
+
+    let mut cpu = CPU::with_memory(RecordingMemory::new());
+    cpu.variant = variant;
+    cpu.program_counter = number(initial, "pc")? as u16;
+    cpu.stack_pointer = number(initial, "s")? as u8;
+    cpu.accumulator = number(initial, "a")? as u8;
+    cpu.x_register = number(initial, "x")? as u8;
+    cpu.y_register = number(initial, "y")? as u8;
+    cpu.status = number(initial, "p")? as u8;
+
+    for (addr, value) in ram(initial)? {
+        cpu.bus.memory.mem_write(addr, value);
+    }
+
+    cpu.step();
+
+    let mut mismatches = Vec::new();
+    let mut check = |field: Field, expected: String, actual: String| {
+        if expected != actual {
+            mismatches.push(Mismatch { test: name.clone(), field, expected, actual });
+        }
+    };
+
+    check(Field::PC, format!("${:04X}", number(expected, "pc")?), format!("${:04X}", cpu.program_counter));
+    check(Field::S, format!("${:02X}", number(expected, "s")?), format!("${:02X}", cpu.stack_pointer));
+    check(Field::A, format!("${:02X}", number(expected, "a")?), format!("${:02X}", cpu.accumulator));
+    check(Field::X, format!("${:02X}", number(expected, "x")?), format!("${:02X}", cpu.x_register));
+    check(Field::Y, format!("${:02X}", number(expected, "y")?), format!("${:02X}", cpu.y_register));
+    check(Field::P, format!("${:02X}", number(expected, "p")?), format!("${:02X}", cpu.status));
+
+    for (addr, value) in ram(expected)? {
+        check(Field::Ram(addr), format!("${:02X}", value), format!("${:02X}", cpu.peek(addr)));
+    }
+
+    let cycles = cycles(test)?;
+    check(Field::Cycles, cycles.len().to_string(), cpu.total_cycles().to_string());
+
+    let accesses = &cpu.bus.accesses;
+    for cycle in 0..cycles.len().max(accesses.len()) {
+        check(Field::Bus(cycle), describe(cycles.get(cycle)), describe(accesses.get(cycle)));
+    }
+
+    Ok(mismatches)
+}
+
+fn describe(access: Option<&(u16, u8, Access)>) -> String {
+    match access {
+        Some((addr, value, Access::Read)) => format!("read ${:02X} from ${:04X}", value, addr),
+        Some((addr, value, Access::Write)) => format!("write ${:02X} to ${:04X}", value, addr),
+        None => "nothing".to_string(),
+    }
+}
+
+fn number(state: &Value, field: &str) -> Result<u64, String> {
+    state[field].as_u64().ok_or(format!("missing field \"{}\"", field))
+}
+
+fn ram(state: &Value) -> Result<Vec<(u16, u8)>, String> {
+    let entries = state["ram"].as_array().ok_or("missing field \"ram\"")?;
+
+    entries.iter().map(|entry| {
+        match (entry[0].as_u64(), entry[1].as_u64()) {
+            (Some(addr), Some(value)) => Ok((addr as u16, value as u8)),
+            _ => Err(format!("malformed ram entry {}", entry)),
+        }
+    }).collect()
+}
+
+fn cycles(test: &Value) -> Result<Vec<(u16, u8, Access)>, String> {
+    let entries = test["cycles"].as_array().ok_or("missing field \"cycles\"")?;
+
+    entries.iter().map(|entry| {
+        let access = match entry[2].as_str() {
+            Some("read") => Access::Read,
+            Some("write") => Access::Write,
+            _ => return Err(format!("malformed cycle {}", entry)),
+        };
+        match (entry[0].as_u64(), entry[1].as_u64()) {
+            (Some(addr), Some(value)) => Ok((addr as u16, value as u8, access)),
+            _ => Err(format!("malformed cycle {}", entry)),
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/nes6502");
+
+    // LDA $10FF,X crossing into page $11
+    const LDA_ABX: &str = r#"[{
+        "name": "bd ff 10",
+        "initial": { "pc": 512, "s": 253, "a": 0, "x": 1, "y": 0, "p": 38,
+                     "ram": [[512, 189], [513, 255], [514, 16], [4096, 0], [4352, 66]] },
+        "final": { "pc": 515, "s": 253, "a": 66, "x": 1, "y": 0, "p": 36,
+                   "ram": [[512, 189], [513, 255], [514, 16], [4096, 0], [4352, 66]] },
+        "cycles": [[512, 189, "read"], [513, 255, "read"], [514, 16, "read"], [4096, 0, "read"], [4352, 66, "read"]]
+    }]"#;
+
+    #[test]
+    #[ignore = "needs the nes6502 set of SingleStepTests in tests/fixtures/nes6502, it isn't in the tree"]
+    fn single_step_tests() {
+        let reports = run_directory(Variant::Ricoh2A03, Path::new(FIXTURES)).unwrap();
+        let failed: Vec<String> = reports.iter().filter(|r| !r.passed()).map(|r| r.to_string()).collect();
+
+        assert!(failed.is_empty(), "\n{}", failed.join("\n"));
+    }
+
+    #[test]
+    fn missing_opcode_files_fail() {
+        let dir = std::env::temp_dir().join(format!("runes-single-step-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("bd.json"), LDA_ABX).unwrap();
+
+        let result = run_directory(Variant::Ricoh2A03, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let Err(error) = result else { panic!("a partial set passed") };
+        assert!(error.contains("missing 255 opcode files (00.json, 01.json,"), "{}", error);
+        assert!(!error.contains("bd.json"));
+    }
+
+    #[test]
+    fn passing_vector() {
+        let report = run_opcode(Variant::Ricoh2A03, 0xBD, LDA_ABX).unwrap();
+
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.tests, 1);
+    }
+
+    #[test]
+    fn mismatches_are_reported_by_field() {
+        let json = LDA_ABX.replace(r#""a": 66"#, r#""a": 67"#).replace(r#"[4352, 66, "read"]]"#, r#"[4352, 66, "read"], [4352, 66, "read"]]"#);
+        let report = run_opcode(Variant::Ricoh2A03, 0xBD, &json).unwrap();
+
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.mismatches[0].field, Field::A);
+        assert_eq!(report.mismatches[0].expected, "$43");
+        assert_eq!(report.mismatches[0].actual, "$42");
+        assert_eq!(report.mismatches[1].field, Field::Cycles);
+        assert_eq!(report.mismatches[1].to_string(), "\"bd ff 10\" cycles: expected 6, got 5");
+        assert_eq!(report.mismatches[2].field, Field::Bus(5));
+        assert_eq!(report.mismatches[2].actual, "nothing");
+    }
+}
@@ -0,0 +1,83 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::cartridge::Cartridge;
+
+// Debugger state that belongs to a game (breakpoints, watches, ...) is kept in a directory named after
+// the CRC32 of its PRG and CHR ROM, so it follows the game when the file is renamed or moved.
+// The directories live in $XDG_DATA_HOME/runes, ~/.local/share/runes or %APPDATA%\runes.
+pub fn rom_data_path(cartridge: &Cartridge, file: &str) -> Option<PathBuf> {
+    Some(data_path(&format!("{:08X}", cartridge.crc32()))?.join(file))
+}
+
+// Settings shared by every game, such as the key mapping, are kept in the runes directory itself
+pub fn data_path(file: &str) -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(base.join("runes").join(file))
+}
+
+// The JSON saved at path, None when nothing was saved there yet
+pub fn read_json(path: &Path) -> Result<Option<Value>, String> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(format!("{}: {}", path.display(), error)),
+    };
+
+    serde_json::from_str(&json).map(Some).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Saves value at path, creating the directories it is in
+pub fn write_json(path: &Path, value: &Value) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::write(path, serde_json::to_string_pretty(value).unwrap()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// CRC-32 (IEEE 802.3) of the chunks one after the other, as used by ROM databases
+pub fn crc32<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for byte in chunks.into_iter().flatten() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32([b"123456789".as_slice()]), 0xCBF43926);
+        assert_eq!(crc32([b"1234".as_slice(), b"56789".as_slice()]), 0xCBF43926);
+        assert_eq!(crc32([]), 0x00000000);
+    }
+
+    #[test]
+    fn json_files() {
+        let path = env::temp_dir().join(format!("runes-storage-{}", std::process::id())).join("test.json");
+
+        assert_eq!(read_json(&path), Ok(None));
+        write_json(&path, &serde_json::json!({ "a": 1 })).unwrap();
+        assert_eq!(read_json(&path), Ok(Some(serde_json::json!({ "a": 1 }))));
+
+        fs::write(&path, "{").unwrap();
+        assert!(read_json(&path).is_err());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
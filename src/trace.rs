@@ -0,0 +1,84 @@
+use crate::cpu::{AddressingMode, Variant, CPU};
+use crate::disassembler::disassemble;
+
+// Formats the state of the CPU before the next instruction executes, in the same layout as Nintendulator's nestest.log
+// e.g. C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+pub fn trace(cpu: &CPU) -> String {
+    let line = disassemble(cpu.variant, |addr| cpu.peek(addr), cpu.program_counter);
+
+    let lo = *line.bytes.get(1).unwrap_or(&0);
+    let word = line.word();
+
+    // Nintendulator follows the operand to the effective address and the value stored there
+    let annotation = match line.instruction.addrmode {
+        AddressingMode::IMP | AddressingMode::ACC | AddressingMode::IMM | AddressingMode::REL => String::new(),
+        AddressingMode::ZP0 => format!(" = {:02X}", cpu.peek(lo as u16)),
+        AddressingMode::ZPX => {
+            let addr = lo.wrapping_add(cpu.x_register);
+            format!(" @ {:02X} = {:02X}", addr, cpu.peek(addr as u16))
+        },
+        AddressingMode::ZPY => {
+            let addr = lo.wrapping_add(cpu.y_register);
+            format!(" @ {:02X} = {:02X}", addr, cpu.peek(addr as u16))
+        },
+        AddressingMode::ABS => match line.bytes[0] {
+            // Jumps don't access their operand
+            0x4C | 0x20 => String::new(),
+            _ => format!(" = {:02X}", cpu.peek(word)),
+        },
+        AddressingMode::ABX => {
+            let addr = word.wrapping_add(cpu.x_register as u16);
+            format!(" @ {:04X} = {:02X}", addr, cpu.peek(addr))
+        },
+        AddressingMode::ABY => {
+            let addr = word.wrapping_add(cpu.y_register as u16);
+            format!(" @ {:04X} = {:02X}", addr, cpu.peek(addr))
+        },
+        AddressingMode::IND => {
+            // Keep the page wrap bug of the indirect jump, the 65C02 fixed it
+            let hi_addr = match cpu.variant {
+                Variant::Cmos65C02 => word.wrapping_add(1),
+                _ => (word & 0xFF00) | (word.wrapping_add(1) & 0x00FF),
+            };
+            let target = (cpu.peek(hi_addr) as u16) << 8 | cpu.peek(word) as u16;
+            format!(" = {:04X}", target)
+        },
+        AddressingMode::IZX => {
+            let ptr = lo.wrapping_add(cpu.x_register);
+            let addr = (cpu.peek(ptr.wrapping_add(1) as u16) as u16) << 8 | cpu.peek(ptr as u16) as u16;
+            format!(" @ {:02X} = {:04X} = {:02X}", ptr, addr, cpu.peek(addr))
+        },
+        AddressingMode::IZY => {
+            let base = (cpu.peek(lo.wrapping_add(1) as u16) as u16) << 8 | cpu.peek(lo as u16) as u16;
+            let addr = base.wrapping_add(cpu.y_register as u16);
+            format!(" = {:04X} @ {:04X} = {:02X}", base, addr, cpu.peek(addr))
+        },
+        AddressingMode::IZP => {
+            let addr = (cpu.peek(lo.wrapping_add(1) as u16) as u16) << 8 | cpu.peek(lo as u16) as u16;
+            format!(" = {:04X} = {:02X}", addr, cpu.peek(addr))
+        },
+        AddressingMode::IAX => {
+            let ptr = word.wrapping_add(cpu.x_register as u16);
+            let target = (cpu.peek(ptr.wrapping_add(1)) as u16) << 8 | cpu.peek(ptr) as u16;
+            format!(" @ {:04X} = {:04X}", ptr, target)
+        },
+    };
+
+    let unofficial = if line.instruction.is_official() { ' ' } else { '*' };
+
+    format!(
+        "{:04X}  {:<8} {}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+        line.addr,
+        line.hex(),
+        unofficial,
+        line.text() + &annotation,
+        cpu.accumulator,
+        cpu.x_register,
+        cpu.y_register,
+        cpu.status,
+        cpu.stack_pointer,
+        cpu.bus.ppu.scanline,
+        cpu.bus.ppu.cycle,
+        cpu.total_cycles(),
+    )
+}
@@ -1,10 +1,26 @@
+use std::path::PathBuf;
+
 use eframe::egui;
 use crate::cpu::CPU;
-use crate::ppu::SYSTEM_PALLETE;
+use crate::ppu::{self, SYSTEM_PALLETE};
 use egui_dock::{DockArea, NodeIndex, Style, Tree};
 
-use crate::opcodes::references;
 use crate::renderer;
+use crate::disassembler;
+use crate::interrupts::IrqSource;
+use crate::debugger::{self, Breakpoint, BreakpointKind, Command, Debugger, BREAKPOINT_KINDS};
+use crate::expression::Expression;
+use crate::storage;
+use crate::hex_editor::{self, ChangeTracker};
+use crate::cdl;
+use crate::profiler::{self, VBLANK_CYCLES};
+use crate::ram_search::{self, Filter, Operand, Operator, RamSearch, Size, View};
+use crate::watch::{self, Format, Watch, WatchList};
+use crate::controller::{self, Adapter, Device, Joypad, Zapper, BUTTONS};
+use crate::keymap::{self, KeyMap};
+
+// NTSC CPU cycles per frame, how far a debugger command runs per update
+const CYCLES_PER_FRAME: u64 = 29781;
 
 pub fn ui(cpu: CPU) -> Result<(), eframe::Error> {
     env_logger::init();
@@ -21,10 +37,133 @@ pub fn ui(cpu: CPU) -> Result<(), eframe::Error> {
 
 struct RunesContext {
     cpu: CPU,
-    page_cpu: u16,
-    page_rom: u16,
 
     chr_rom_texture: Option<egui::TextureHandle>,
+    game_texture: Option<egui::TextureHandle>,
+
+    debugger: Debugger,
+    run_to: String, // Address typed in for Run to
+    show_cdl: bool, // Color ROM bytes and disassembly by code/data logger flags
+    memory_editor: MemoryEditor,
+    profiler_addresses: bool, // Per address table instead of per routine
+    profiler_sort: (usize, bool), // Column and descending
+    profiler_export: Option<String>, // Result of the last export
+    breakpoint_form: BreakpointForm,
+    ram_search: RamSearch,
+    ram_search_form: RamSearchForm,
+    watches: WatchList,
+    watch_form: WatchForm,
+    keymap: KeyMap,
+    binding: Option<(usize, usize)>, // Player and button waiting for a key in the Controllers tab
+}
+
+// State of the hex editor over the CPU address space
+#[derive(Default)]
+struct MemoryEditor {
+    goto: String,
+    scroll_to: Option<u16>, // Row to bring into view on the next frame
+    selected: Option<u16>,
+    edit: String, // Value typed in for the selected byte
+    changes: ChangeTracker,
+    table: Option<hex_editor::Table>, // Text column decoding, ASCII without one
+    table_path: String,
+    search: String,
+    results: Vec<u16>,
+    result: usize, // Match shown
+    message: Option<String>,
+}
+
+impl MemoryEditor {
+    fn select(&mut self, addr: u16, byte: u8) {
+        self.selected = Some(addr);
+        self.edit = format!("{:02X}", byte);
+    }
+}
+
+// ROM can't be written without a mapper, the bus ignores it
+fn memory_editable(addr: u16) -> bool {
+    addr < 0x8000
+}
+
+// Pixel of the NES picture under pos, for a picture drawn scaled into rect
+fn nes_coordinates(rect: egui::Rect, pos: egui::Pos2) -> Option<(u16, u16)> {
+    if !rect.contains(pos) {
+        return None;
+    }
+
+    let x = ((pos.x - rect.min.x) / rect.width() * ppu::WIDTH as f32) as u16;
+    let y = ((pos.y - rect.min.y) / rect.height() * ppu::HEIGHT as f32) as u16;
+    Some((x.min(ppu::WIDTH as u16 - 1), y.min(ppu::HEIGHT as u16 - 1)))
+}
+
+fn row_char_width(ui: &egui::Ui) -> f32 {
+    ui.fonts(|fonts| fonts.glyph_width(&egui::TextStyle::Monospace.resolve(ui.style()), '0'))
+}
+
+// Display format picker of the Watch tab, true when another one was picked
+fn format_combo(ui: &mut egui::Ui, id: impl std::hash::Hash, format: &mut Format) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_source(id)
+        .width(60.0)
+        .selected_text(format.to_string())
+        .show_ui(ui, |ui| {
+            for option in watch::FORMATS {
+                changed |= ui.selectable_value(format, option, option.to_string()).changed();
+            }
+        });
+    changed
+}
+
+// Fields of the "add breakpoint" row in the Breakpoints tab
+struct BreakpointForm {
+    kind: BreakpointKind,
+    range: String,
+    condition: String,
+    hit_count: String,
+    error: Option<String>,
+}
+
+// Filter row of the RAM Search tab
+struct RamSearchForm {
+    operator: Operator,
+    operand: usize, // Index in OPERANDS
+    value: String,
+    error: Option<String>,
+}
+
+// Operands of the filter row, the ones after the first take the typed value
+const OPERANDS: [&str; 3] = ["previous value", "value", "previous value +"];
+
+impl Default for RamSearchForm {
+    fn default() -> Self {
+        RamSearchForm { operator: Operator::NotEqual, operand: 0, value: String::new(), error: None }
+    }
+}
+
+impl RamSearchForm {
+    fn filter(&self, view: &View) -> Result<Filter, String> {
+        let operand = match self.operand {
+            0 => Operand::Previous,
+            1 => Operand::Value(view.value(view.parse(&self.value)?)),
+            _ => Operand::ChangedBy(self.value.trim().parse().map_err(|_| format!("invalid change '{}'", self.value.trim()))?),
+        };
+
+        Ok(Filter { operator: self.operator, operand })
+    }
+}
+
+// Fields of the "add watch" row in the Watch tab
+struct WatchForm {
+    expression: String,
+    label: String,
+    format: Format,
+    error: Option<String>,
+}
+
+impl Default for WatchForm {
+    fn default() -> Self {
+        WatchForm { expression: String::new(), label: String::new(), format: Format::U8, error: None }
+    }
 }
 
 impl egui_dock::TabViewer for RunesContext {
@@ -32,18 +171,28 @@ impl egui_dock::TabViewer for RunesContext {
 
     fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
         match tab.as_str() {
-            "CPU Memory Inspector" => self.cpu_memory_inspector(ui),
+            "Memory Editor" => self.memory_editor(ui),
             "Game" => self.game(ui),
             "CPU Register Inspector" => self.cpu_register_inspector(ui),
             "CPU Debug Inspector" => self.cpu_debug_inspector(ui),
-            "ROM Memory Inspector" => self.rom_memory_inspector(ui),
             "ROM Header Inspector" => self.rom_header_inspector(ui),
             "CHR ROM Inspector" => self.chr_rom_inspector(ui),
+            "Breakpoints" => self.breakpoints(ui),
+            "Call Stack" => self.call_stack(ui),
+            "Profiler" => self.profiler(ui),
+            "RAM Search" => self.ram_search(ui),
+            "Watch" => self.watch(ui),
+            "Controllers" => self.controllers(ui),
             _ => {}
         }
     }
 
     fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
+        // Point at the tab explaining why the emulation paused
+        if tab == "Breakpoints" && self.debugger.hit.is_some() {
+            return egui::RichText::new(tab.as_str()).color(egui::Color32::RED).into();
+        }
+
         tab.as_str().into()
     }
 
@@ -51,49 +200,149 @@ impl egui_dock::TabViewer for RunesContext {
 }
 
 impl RunesContext {
-    fn cpu_memory_inspector(&mut self, ui: &mut egui::Ui) {
-        // change style to monospace
-        ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+    fn memory_editor(&mut self, ui: &mut egui::Ui) {
+        let editor = &mut self.memory_editor;
 
-        // page selector
         ui.horizontal(|ui| {
-            ui.label("Page: ");
-            ui.add(egui::DragValue::new(&mut self.page_cpu).speed(1.0).clamp_range(0..=0x07));
+            ui.label("Go to");
+            let response = ui.add(egui::TextEdit::singleline(&mut editor.goto).hint_text("$0300").desired_width(60.0));
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                match debugger::parse_range(&editor.goto) {
+                    Ok((addr, _)) => {
+                        editor.scroll_to = Some(addr);
+                        editor.selected = Some(addr);
+                        editor.message = None;
+                    },
+                    Err(error) => editor.message = Some(error),
+                }
+            }
+
+            ui.separator();
+            ui.label("Find");
+            let response = ui.add(egui::TextEdit::singleline(&mut editor.search).hint_text("A9 ?? 8D or \"text\"").desired_width(140.0));
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let cpu = &self.cpu;
+                match hex_editor::parse_pattern(&editor.search, editor.table.as_ref()) {
+                    Ok(pattern) => {
+                        editor.results = hex_editor::search(|addr| cpu.peek(addr), &pattern);
+                        editor.result = 0;
+                        editor.scroll_to = editor.results.first().copied();
+                        editor.message = Some(format!("{} matches", editor.results.len()));
+                    },
+                    Err(error) => editor.message = Some(error),
+                }
+            }
+            if !editor.results.is_empty() && ui.button("Next").clicked() {
+                editor.result = (editor.result + 1) % editor.results.len();
+                editor.scroll_to = Some(editor.results[editor.result]);
+                editor.message = Some(format!("Match {} of {}", editor.result + 1, editor.results.len()));
+            }
+
+            ui.separator();
+            ui.label("Table");
+            ui.add(egui::TextEdit::singleline(&mut editor.table_path).hint_text("game.tbl").desired_width(100.0));
+            if ui.button("Load").clicked() {
+                match std::fs::read_to_string(&editor.table_path).map_err(|e| e.to_string()).and_then(|text| hex_editor::Table::parse(&text)) {
+                    Ok(table) => {
+                        editor.table = Some(table);
+                        editor.message = None;
+                    },
+                    Err(error) => editor.message = Some(format!("{}: {}", editor.table_path, error)),
+                }
+            }
+            if editor.table.is_some() && ui.button("ASCII").clicked() {
+                editor.table = None;
+            }
         });
 
-        for addr in 0..=15 {
-            ui.horizontal(|ui| {
-                ui.label(format!("{:02X}{:2X}0", self.page_cpu, addr));
-                ui.separator();
-                for i in 0..=15 {
-                    // format as hex
-                    // only print when read from page 8000 ~ 8010
-                    ui.label(format!("{:02X}", self.cpu.bus.cpu_vram[(self.page_cpu << 8 | addr << 4 | i) as usize]));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_cdl, "Color ROM by CDL");
+            if ui.button("Save CDL").clicked() {
+                self.save_cdl();
+            }
+            if self.show_cdl {
+                for (name, flags) in [("code", cdl::CODE), ("data", cdl::DATA), ("both", cdl::CODE | cdl::DATA), ("unused", 0)] {
+                    ui.colored_label(cdl_color(flags), name);
                 }
-            });
+                ui.label(cdl::Coverage::new(&self.cpu.bus.prg_log, &self.cpu.bus.ppu.chr_log).to_string());
+            }
+        });
+
+        if let Some(message) = &self.memory_editor.message {
+            ui.label(message);
         }
-    }
 
-    fn rom_memory_inspector(&mut self, ui: &mut egui::Ui) {
-        // change style to monospace
+        ui.separator();
         ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
 
-        // page selector
-        ui.horizontal(|ui| {
-            ui.label("Page: ");
-            ui.add(egui::DragValue::new(&mut self.page_rom).speed(1.0).clamp_range(0x80..=0xFF));
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let mut scroll = egui::ScrollArea::vertical().auto_shrink([false, false]);
+        if let Some(addr) = self.memory_editor.scroll_to.take() {
+            scroll = scroll.vertical_scroll_offset((addr >> 4) as f32 * (row_height + ui.spacing().item_spacing.y));
+        }
+
+        scroll.show_rows(ui, row_height, 0x1000, |ui, rows| {
+            for row in rows {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{:04X}", row << 4));
+                    ui.separator();
+
+                    let mut text = String::new();
+                    for column in 0..16 {
+                        let addr = (row << 4 | column) as u16;
+                        let byte = self.cpu.peek(addr);
+                        text += &match &self.memory_editor.table {
+                            Some(table) => table.text(byte),
+                            None => hex_editor::ascii(byte).to_string(),
+                        };
+                        self.memory_byte(ui, addr, byte);
+                    }
+
+                    ui.separator();
+                    ui.label(text);
+                });
+            }
         });
+    }
 
-        for addr in 0..=15 {
-            ui.horizontal(|ui| {
-                ui.label(format!("{:02X}{:2X}0", self.page_rom, addr));
-                ui.separator();
-                for i in 0..=15 {
-                    // format as hex
-                    // only print when read from page 8000 ~ 8010
-                    ui.label(format!("{:02X}", self.cpu.bus.read_prg_rom(self.page_rom << 8 | addr << 4 | i)));
+    // One byte of the hex editor, an edit field once clicked
+    fn memory_byte(&mut self, ui: &mut egui::Ui, addr: u16, byte: u8) {
+        let editor = &mut self.memory_editor;
+
+        if editor.selected == Some(addr) && memory_editable(addr) {
+            let response = ui.add(egui::TextEdit::singleline(&mut editor.edit).desired_width(row_char_width(ui) * 2.0));
+            if response.lost_focus() {
+                if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    match u8::from_str_radix(editor.edit.trim(), 16) {
+                        Ok(value) => {
+                            self.cpu.bus.mem_write(addr, value);
+                            editor.select(addr.wrapping_add(1), self.cpu.peek(addr.wrapping_add(1)));
+                        },
+                        Err(_) => editor.message = Some(format!("invalid byte '{}'", editor.edit.trim())),
+                    }
+                } else {
+                    editor.selected = None;
                 }
-            });
+            } else if !response.has_focus() {
+                response.request_focus();
+            }
+            return;
+        }
+
+        let mut text = egui::RichText::new(format!("{:02X}", byte));
+        if editor.selected == Some(addr) {
+            text = text.background_color(egui::Color32::DARK_BLUE);
+        }
+        if let Some(age) = editor.changes.age(addr) {
+            // Red fading out over a second
+            let fade = age as f32 / hex_editor::CHANGE_FRAMES as f32;
+            text = text.color(egui::Color32::from_rgb(255, (80.0 + 175.0 * fade) as u8, (80.0 + 175.0 * fade) as u8));
+        } else if self.show_cdl && addr >= 0x8000 {
+            text = text.color(cdl_color(self.cpu.bus.prg_log[self.cpu.bus.prg_rom_offset(addr)]));
+        }
+
+        if ui.add(egui::Label::new(text).sense(egui::Sense::click())).clicked() {
+            editor.select(addr, byte);
         }
     }
 
@@ -101,6 +350,11 @@ impl RunesContext {
         // change style to monospace
         ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
 
+        if let Some(addr) = self.cpu.jammed {
+            ui.colored_label(egui::Color32::RED, format!("CPU jammed at ${:04X}, press R to reset", addr));
+            ui.separator();
+        }
+
         ui.horizontal(|ui| {
             ui.label("A: ");
             ui.label(format!("{:02X}", self.cpu.accumulator));
@@ -131,11 +385,85 @@ impl RunesContext {
             ui.label(format!("{:08b}", self.cpu.status));
         });
 
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("NMI: ");
+            ui.label(match (self.cpu.bus.interrupts.nmi(), self.cpu.nmi_pending) {
+                (_, true) => "pending",
+                (true, false) => "asserted",
+                (false, false) => "-",
+            });
+        });
+
+        // Every source currently holding the IRQ line
+        let sources = self.cpu.bus.interrupts.irq_sources();
+        ui.horizontal(|ui| {
+            ui.label("IRQ: ");
+            if sources.is_empty() {
+                ui.label("-");
+            } else {
+                ui.colored_label(egui::Color32::YELLOW, sources.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", "));
+            }
+        });
+
+        let mut external = sources.contains(&IrqSource::External);
+        if ui.checkbox(&mut external, "Assert external IRQ").changed() {
+            self.cpu.bus.interrupts.set_irq(IrqSource::External, external);
+        }
     }
 
     fn cpu_debug_inspector(&mut self, ui: &mut egui::Ui) {
-        ui.label(format!("Opcode {}", references::INSTRUCTION_LOOKUP[self.cpu.opcode as usize]));       
+        ui.horizontal(|ui| {
+            let run = if self.debugger.running() { "Pause (F5)" } else { "Run (F5)" };
+            if ui.button(run).clicked() {
+                self.command(Command::Run);
+            }
+            if ui.button("Step over (F10)").clicked() {
+                self.command(Command::StepOver);
+            }
+            if ui.button("Step out (Shift+F11)").clicked() {
+                self.command(Command::StepOut);
+            }
+            if ui.button("Scanline (F7)").clicked() {
+                self.command(Command::StepScanline);
+            }
+            if ui.button("Frame (F8)").clicked() {
+                self.command(Command::StepFrame);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.run_to).hint_text("$8000").desired_width(60.0));
+            if ui.button("Run to").clicked() {
+                match debugger::parse_range(&self.run_to) {
+                    Ok((addr, _)) => self.command(Command::RunTo(addr)),
+                    Err(error) => log::warn!("{}", error),
+                }
+            }
+            ui.label(format!("Scanline {} cycle {} frame {}", self.cpu.bus.ppu.scanline, self.cpu.bus.ppu.cycle, self.cpu.bus.ppu.frame));
+        });
+
+        ui.separator();
+
+        ui.label(format!("Opcode {}", self.cpu.instruction()));
         ui.label(format!("Cycles: {:?}", self.cpu.cycles));
+
+        ui.separator();
+
+        // change style to monospace
+        ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+
+        let cpu = &self.cpu;
+        for line in disassembler::disassemble_range(cpu.variant, |addr| cpu.peek(addr), cpu.program_counter, 16) {
+            if line.addr == cpu.program_counter {
+                ui.colored_label(egui::Color32::YELLOW, format!("> {}", line));
+            } else if self.show_cdl && line.addr >= 0x8000 {
+                ui.colored_label(cdl_color(cpu.bus.prg_log[cpu.bus.prg_rom_offset(line.addr)]), format!("  {}", line));
+            } else {
+                ui.label(format!("  {}", line));
+            }
+        }
     }
 
     fn rom_header_inspector(&mut self, ui: &mut egui::Ui) {
@@ -195,8 +523,577 @@ impl RunesContext {
         
     }
 
+    fn call_stack(&mut self, ui: &mut egui::Ui) {
+        ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+
+        // Innermost frame first, where the CPU is now
+        ui.colored_label(egui::Color32::YELLOW, format!("> ${:04X}", self.cpu.program_counter));
+        for frame in self.cpu.call_stack.frames.iter().rev() {
+            ui.label(format!("  {}", frame));
+        }
+
+        if !self.cpu.call_stack.warnings.is_empty() {
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.colored_label(egui::Color32::YELLOW, "Stack manipulated by hand:");
+                if ui.small_button("Clear").clicked() {
+                    self.cpu.call_stack.warnings.clear();
+                }
+            });
+
+            for warning in self.cpu.call_stack.warnings.iter().rev() {
+                ui.colored_label(egui::Color32::YELLOW, warning);
+            }
+        }
+    }
+
+    fn profiler(&mut self, ui: &mut egui::Ui) {
+        let profiler = &mut self.debugger.profiler;
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut profiler.enabled, "Enabled");
+            if ui.button("Clear").clicked() {
+                profiler.clear();
+            }
+            ui.separator();
+            ui.radio_value(&mut self.profiler_addresses, false, "Routines");
+            ui.radio_value(&mut self.profiler_addresses, true, "Addresses");
+            ui.separator();
+            if ui.button("Export CSV").clicked() {
+                let csv = if self.profiler_addresses { profiler.addresses_csv() } else { profiler.routines_csv() };
+                let file = if self.profiler_addresses { "profile-addresses.csv" } else { "profile-routines.csv" };
+                self.profiler_export = Some(export(&self.cpu, file, &csv));
+            }
+            if ui.button("Export collapsed stacks").clicked() {
+                self.profiler_export = Some(export(&self.cpu, "profile.folded", &profiler.collapsed_stacks()));
+            }
+        });
+
+        if let Some(message) = &self.profiler_export {
+            ui.label(message);
+        }
+
+        // NMI handler time against the vblank budget
+        let runs = &profiler.nmi_runs;
+        if let Some(last) = runs.back() {
+            let max = runs.iter().map(|run| run.cycles).max().unwrap_or(0);
+            let overruns = runs.iter().filter(|run| run.overrun).count();
+            let text = format!("NMI: {} cycles last frame, {} max, {} of {} frames over the {} cycle vblank",
+                last.cycles, max, overruns, runs.len(), VBLANK_CYCLES);
+            if last.overrun {
+                ui.colored_label(egui::Color32::RED, text);
+            } else {
+                ui.label(text);
+            }
+        }
+
+        ui.separator();
+        ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+
+        let columns = if self.profiler_addresses {
+            ["Address", "Instructions", "Cycles", "%"]
+        } else {
+            ["Routine", "Calls", "Exclusive", "Inclusive"]
+        };
+
+        // A row is a name and three counts, the percentage of the address view is derived from cycles
+        let mut rows: Vec<(u16, String, [u64; 3])> = if self.profiler_addresses {
+            profiler.addresses.iter().enumerate().filter(|(_, stats)| stats.instructions > 0)
+                .map(|(addr, stats)| (addr as u16, format!("${:04X}", addr), [stats.instructions, stats.cycles, stats.cycles]))
+                .collect()
+        } else {
+            profiler.routines.iter()
+                .map(|(&routine, stats)| (routine.1, profiler::routine_name(routine), [stats.calls, stats.exclusive, stats.inclusive]))
+                .collect()
+        };
+
+        let (column, descending) = self.profiler_sort;
+        rows.sort_by(|a, b| {
+            let ordering = if column == 0 { a.0.cmp(&b.0) } else { a.2[column - 1].cmp(&b.2[column - 1]) };
+            if descending { ordering.reverse() } else { ordering }
+        });
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("profiler").striped(true).show(ui, |ui| {
+                for (index, name) in columns.iter().enumerate() {
+                    let arrow = match self.profiler_sort {
+                        (sorted, true) if sorted == index => " v",
+                        (sorted, false) if sorted == index => " ^",
+                        _ => "",
+                    };
+                    if ui.button(format!("{}{}", name, arrow)).clicked() {
+                        self.profiler_sort = (index, self.profiler_sort != (index, true));
+                    }
+                }
+                ui.label(if self.profiler_addresses { "" } else { "%" });
+                ui.end_row();
+
+                for (_, name, counts) in rows.iter().take(256) {
+                    ui.label(name);
+                    ui.label(counts[0].to_string());
+                    ui.label(counts[1].to_string());
+                    if self.profiler_addresses {
+                        ui.label(format!("{:.2}", profiler.percent(counts[2])));
+                        ui.label("");
+                    } else {
+                        ui.label(counts[2].to_string());
+                        ui.label(format!("{:.2}", profiler.percent(counts[1])));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn breakpoints(&mut self, ui: &mut egui::Ui) {
+        if let Some(hit) = &self.debugger.hit {
+            ui.colored_label(egui::Color32::RED, format!("Paused: {}", hit));
+        } else if self.debugger.running() {
+            ui.label("Running, F5 to pause");
+        } else {
+            ui.label("Paused, F5 to run");
+        }
+
+        ui.separator();
+
+        // New breakpoint
+        let form = &mut self.breakpoint_form;
+        let added = ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("breakpoint-kind")
+                .selected_text(form.kind.to_string())
+                .show_ui(ui, |ui| {
+                    for kind in BREAKPOINT_KINDS {
+                        ui.selectable_value(&mut form.kind, kind, kind.to_string());
+                    }
+                });
+            ui.add(egui::TextEdit::singleline(&mut form.range).hint_text("$8000-$80FF").desired_width(100.0));
+            ui.add(egui::TextEdit::singleline(&mut form.condition).hint_text("A == #$40 && X > 3").desired_width(160.0));
+            ui.add(egui::TextEdit::singleline(&mut form.hit_count).hint_text("hit count").desired_width(60.0));
+
+            ui.button("Add").clicked()
+        }).inner;
+
+        if added {
+            match form.breakpoint() {
+                Ok(breakpoint) => {
+                    self.debugger.breakpoints.push(breakpoint);
+                    self.breakpoint_form = BreakpointForm::default();
+                    self.save_breakpoints();
+                },
+                Err(error) => form.error = Some(error),
+            }
+        }
+
+        if let Some(error) = &self.breakpoint_form.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.separator();
+
+        ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+
+        let hit = self.debugger.hit.as_ref().map(|hit| hit.index);
+        let mut changed = false;
+        let mut removed = None;
+
+        for (index, breakpoint) in self.debugger.breakpoints.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui.checkbox(&mut breakpoint.enabled, "").changed();
+
+                let text = format!("{} ({} hits)", breakpoint, breakpoint.hits);
+                if hit == Some(index) {
+                    ui.colored_label(egui::Color32::RED, format!("> {}", text));
+                } else {
+                    ui.label(format!("  {}", text));
+                }
+
+                if ui.small_button("x").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+
+        if let Some(index) = removed {
+            self.debugger.breakpoints.remove(index);
+            self.debugger.hit = None;
+            changed = true;
+        }
+
+        if changed {
+            self.save_breakpoints();
+        }
+    }
+
+    fn ram_search(&mut self, ui: &mut egui::Ui) {
+        let cpu = &self.cpu;
+        let search = &mut self.ram_search;
+        let form = &mut self.ram_search_form;
+
+        ui.horizontal(|ui| {
+            let view = search.view;
+            ui.radio_value(&mut search.view.size, Size::Byte, "8-bit");
+            ui.radio_value(&mut search.view.size, Size::Word, "16-bit");
+            ui.checkbox(&mut search.view.signed, "Signed");
+            // Candidates are addresses of the old size, start over
+            if search.view.size != view.size {
+                search.snapshot(|addr| cpu.peek(addr));
+            }
+
+            ui.separator();
+            if ui.button("Snapshot").clicked() {
+                search.snapshot(|addr| cpu.peek(addr));
+                form.error = None;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Current value");
+            egui::ComboBox::from_id_source("ram-search-operator")
+                .width(40.0)
+                .selected_text(form.operator.to_string())
+                .show_ui(ui, |ui| {
+                    for operator in ram_search::OPERATORS {
+                        ui.selectable_value(&mut form.operator, operator, operator.to_string());
+                    }
+                });
+            egui::ComboBox::from_id_source("ram-search-operand")
+                .selected_text(OPERANDS[form.operand])
+                .show_ui(ui, |ui| {
+                    for (index, operand) in OPERANDS.iter().enumerate() {
+                        ui.selectable_value(&mut form.operand, index, *operand);
+                    }
+                });
+            if form.operand > 0 {
+                ui.add(egui::TextEdit::singleline(&mut form.value).hint_text(if form.operand == 1 { "$40" } else { "-1" }).desired_width(60.0));
+            }
+
+            if ui.add_enabled(!search.candidates.is_empty(), egui::Button::new("Filter")).clicked() {
+                match form.filter(&search.view) {
+                    Ok(filter) => {
+                        search.filter(|addr| cpu.peek(addr), filter);
+                        form.error = None;
+                    },
+                    Err(error) => form.error = Some(error),
+                }
+            }
+        });
+
+        if let Some(error) = &form.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+        let view = search.view;
+        let value = |addr: u16, view: View| {
+            let raw = view.raw(|addr| cpu.peek(addr), addr);
+            format!("{:>6} {}", view.value(raw), view.hex(raw))
+        };
+
+        if !search.cheats.is_empty() {
+            ui.separator();
+            let mut removed = None;
+            for (index, cheat) in search.cheats.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut cheat.enabled, "");
+                    let cheat_view = View { size: cheat.size, signed: false };
+                    ui.label(format!("${:04X} frozen at {}", cheat.addr, cheat_view.hex(cheat.value)));
+                    if ui.small_button("x").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                search.cheats.remove(index);
+            }
+        }
+
+        ui.separator();
+        ui.label(format!("{} candidates after {} filters", search.candidates.len(), search.filters));
+
+        let mut watched = None;
+        let mut frozen = None;
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace).max(ui.spacing().interact_size.y);
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show_rows(ui, row_height, search.candidates.len(), |ui, rows| {
+            for candidate in &search.candidates[rows] {
+                ui.horizontal(|ui| {
+                    ui.label(format!("${:04X} {} (was {})", candidate.addr, value(candidate.addr, view), view.value(candidate.previous)));
+                    if ui.small_button("Watch").clicked() {
+                        watched = Some(candidate.addr);
+                    }
+                    if ui.small_button("Freeze").clicked() {
+                        frozen = Some(candidate.addr);
+                    }
+                });
+            }
+        });
+
+        if let Some(addr) = frozen {
+            search.freeze(|addr| cpu.peek(addr), addr);
+        }
+        if let Some(addr) = watched {
+            self.watches.watches.push(Watch::address(addr, view));
+            self.save_watches();
+        }
+    }
+
+    fn watch(&mut self, ui: &mut egui::Ui) {
+        // New watch
+        let form = &mut self.watch_form;
+        let added = ui.horizontal(|ui| {
+            let response = ui.add(egui::TextEdit::singleline(&mut form.expression).hint_text("[$0300] + [$0301]*256").desired_width(180.0));
+            ui.add(egui::TextEdit::singleline(&mut form.label).hint_text("label").desired_width(100.0));
+            format_combo(ui, "watch-format", &mut form.format);
+
+            ui.button("Add").clicked() || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+        }).inner;
+
+        if added {
+            match Expression::parse(&form.expression) {
+                Ok(expression) => {
+                    self.watches.watches.push(Watch::new(&form.label, expression, form.format));
+                    self.watch_form = WatchForm { format: form.format, ..WatchForm::default() };
+                    self.watches.update(&self.cpu);
+                    self.save_watches();
+                },
+                Err(error) => form.error = Some(error),
+            }
+        }
+
+        if let Some(error) = &self.watch_form.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.separator();
+
+        let mut changed = false;
+        let mut removed = None;
+
+        egui::Grid::new("watches").striped(true).show(ui, |ui| {
+            for (index, watch) in self.watches.watches.iter_mut().enumerate() {
+                changed |= ui.add(egui::TextEdit::singleline(&mut watch.label).desired_width(100.0)).lost_focus();
+                ui.monospace(watch.expression.source());
+
+                let mut text = egui::RichText::new(watch.text()).monospace();
+                if let Some(age) = watch.age() {
+                    // Red fading out over a second, like the hex editor
+                    let fade = age as f32 / hex_editor::CHANGE_FRAMES as f32;
+                    text = text.color(egui::Color32::from_rgb(255, (80.0 + 175.0 * fade) as u8, (80.0 + 175.0 * fade) as u8));
+                }
+                ui.label(text);
+
+                changed |= format_combo(ui, ("watch-format", index), &mut watch.format);
+                if ui.small_button("x").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(index) = removed {
+            self.watches.watches.remove(index);
+            changed = true;
+        }
+
+        if changed {
+            self.save_watches();
+        }
+    }
+
+    fn controllers(&mut self, ui: &mut egui::Ui) {
+        // The next key pressed goes to the button being bound, Escape cancels
+        if let Some((player, button)) = self.binding {
+            let pressed = ui.input(|i| i.events.iter().find_map(|event| match event {
+                egui::Event::Key { key, pressed: true, .. } => Some(*key),
+                _ => None,
+            }));
+
+            match pressed {
+                Some(egui::Key::Escape) => self.binding = None,
+                Some(key) if keymap::mappable(key) => {
+                    self.keymap.players[player][button] = key;
+                    self.binding = None;
+                    if let Err(error) = self.keymap.save() {
+                        log::warn!("Could not save the key mapping: {}", error);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        ui.horizontal_top(|ui| {
+            for (player, mapping) in self.keymap.players.iter().enumerate() {
+                ui.vertical(|ui| {
+                    ui.label(format!("Player {}", player + 1));
+                    egui::Grid::new(("keymap", player)).show(ui, |ui| {
+                        for (index, button) in BUTTONS.iter().enumerate() {
+                            ui.label(button.to_string());
+                            let text = if self.binding == Some((player, index)) { "press a key" } else { mapping[index].name() };
+                            if ui.button(text).clicked() {
+                                self.binding = Some((player, index));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+                ui.separator();
+            }
+        });
+
+        if self.binding.is_some() {
+            ui.label("Escape cancels, Space and R are kept for stepping and reset");
+        }
+
+        ui.separator();
+        let controllers = &mut self.cpu.bus.controllers;
+        ui.horizontal(|ui| {
+            ui.label("Players 3 and 4");
+            for adapter in controller::ADAPTERS {
+                if ui.radio_value(&mut controllers.adapter, adapter, adapter.to_string()).clicked() && adapter != Adapter::None {
+                    // Players 1 and 2 plug their controllers into the adapter
+                    controllers.ports[1] = Device::Joypad(Joypad::default());
+                }
+            }
+        });
+
+        // The Zapper only goes straight into the port
+        if controllers.adapter == Adapter::None {
+            ui.horizontal(|ui| {
+                ui.label("Port 2");
+                let port = &mut controllers.ports[1];
+                let zapper = matches!(port, Device::Zapper(_));
+                if ui.radio(!zapper, "Controller").clicked() && zapper {
+                    *port = Device::Joypad(Joypad::default());
+                }
+                if ui.radio(zapper, "Zapper").clicked() && !zapper {
+                    *port = Device::Zapper(Zapper::default());
+                }
+            });
+            if matches!(controllers.ports[1], Device::Zapper(_)) {
+                ui.label("Aim with the mouse over the Game tab, the left button pulls the trigger");
+            }
+        }
+    }
+
+    fn save_watches(&self) {
+        if let Err(error) = self.watches.save() {
+            log::warn!("Could not save watches: {}", error);
+        }
+    }
+
+    // Run pauses when already running, every other command starts over
+    fn command(&mut self, command: Command) {
+        if command == Command::Run && self.debugger.running() {
+            self.debugger.pause();
+        } else {
+            self.debugger.start(&self.cpu, command);
+        }
+    }
+
+    fn save_cdl(&self) {
+        if let Err(error) = self.cpu.bus.save_cdl() {
+            log::warn!("Could not save the code/data log: {}", error);
+        }
+    }
+
+    fn save_breakpoints(&self) {
+        if let Err(error) = self.debugger.save() {
+            log::warn!("Could not save breakpoints: {}", error);
+        }
+    }
+
     fn game(&mut self, ui: &mut egui::Ui) {
-        ui.label("Game");
+        // What the PPU has output so far, the rest of the picture is still the previous frame
+        let mut renderer = renderer::PPURenderer::new();
+        for (index, color) in self.cpu.bus.ppu.frame_buffer.iter().enumerate() {
+            renderer.set_pixel(index % ppu::WIDTH, index / ppu::WIDTH, SYSTEM_PALLETE[*color as usize]);
+        }
+
+        let texture = match &mut self.game_texture {
+            Some(texture) => {
+                texture.set(renderer.get_color_image(), egui::TextureOptions::NEAREST);
+                texture
+            },
+            None => self.game_texture.insert(
+                ui.ctx().load_texture("game-texture", renderer.get_color_image(), egui::TextureOptions::NEAREST)),
+        };
+
+        // As large as the tab allows, keeping the aspect ratio
+        let available = ui.available_size();
+        let scale = (available.x / ppu::WIDTH as f32).min(available.y / ppu::HEIGHT as f32).max(0.0);
+        let size = egui::vec2(ppu::WIDTH as f32 * scale, ppu::HEIGHT as f32 * scale);
+        let response = ui.add(egui::Image::new(texture.id(), size).sense(egui::Sense::click()));
+
+        // The Zapper points where the mouse is and fires with the left button
+        if let Some(zapper) = self.cpu.bus.controllers.zapper_mut() {
+            zapper.aim = response.hover_pos().and_then(|pos| nes_coordinates(response.rect, pos));
+            zapper.trigger = zapper.aim.is_some() && ui.input(|i| i.pointer.primary_down());
+        }
+    }
+}
+
+// What was saved at path, the defaults when there is no data directory or the file can't be loaded
+fn load_or_default<T: Default>(path: Option<PathBuf>, load: fn(PathBuf) -> Result<T, String>, what: &str) -> T {
+    match path.map(load) {
+        Some(Ok(loaded)) => loaded,
+        Some(Err(error)) => {
+            log::warn!("Could not load {}: {}", what, error);
+            T::default()
+        },
+        None => T::default(),
+    }
+}
+
+// Writes a report to the game's data directory, returning what happened for the UI
+fn export(cpu: &CPU, file: &str, contents: &str) -> String {
+    let Some(path) = storage::rom_data_path(&cpu.bus.cartridge, file) else {
+        return "No data directory to export to".to_string();
+    };
+
+    let result = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(&path, contents));
+    match result {
+        Ok(()) => format!("Exported {}", path.display()),
+        Err(error) => format!("Could not export {}: {}", path.display(), error),
+    }
+}
+
+// How the code/data logger saw a PRG ROM byte
+fn cdl_color(flags: u8) -> egui::Color32 {
+    match (flags & cdl::CODE != 0, flags & cdl::DATA != 0) {
+        (true, true) => egui::Color32::LIGHT_YELLOW,
+        (true, false) => egui::Color32::LIGHT_GREEN,
+        (false, true) => egui::Color32::LIGHT_BLUE,
+        (false, false) => egui::Color32::DARK_GRAY,
+    }
+}
+
+impl Default for BreakpointForm {
+    fn default() -> Self {
+        BreakpointForm {
+            kind: BreakpointKind::Execute,
+            range: String::new(),
+            condition: String::new(),
+            hit_count: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl BreakpointForm {
+    fn breakpoint(&self) -> Result<Breakpoint, String> {
+        let (start, end) = debugger::parse_range(&self.range)?;
+        let mut breakpoint = Breakpoint::new(self.kind, start, end);
+
+        if !self.condition.trim().is_empty() {
+            breakpoint.condition = Some(Expression::parse(&self.condition)?);
+        }
+
+        if !self.hit_count.trim().is_empty() {
+            let count = self.hit_count.trim().parse().map_err(|_| format!("invalid hit count '{}'", self.hit_count.trim()))?;
+            breakpoint.hit_count = Some(count);
+        }
+
+        Ok(breakpoint)
     }
 }
 
@@ -210,22 +1107,45 @@ impl RunesApp {
     fn new(mut cpu: CPU) -> Self {
         let mut tree = Tree::new(vec!["Game".to_owned()]);
 
-        let [game_node_index , cpu_memory_inspector_node_index] = tree.split_right(NodeIndex::root(), 0.78 ,vec!["CPU Memory Inspector".to_owned()]);
+        let [game_node_index , memory_editor_node_index] = tree.split_right(NodeIndex::root(), 0.78 ,vec!["Memory Editor".to_owned(), "RAM Search".to_owned()]);
 
         tree.split_left(game_node_index, 0.3, vec!["CHR ROM Inspector".to_owned()]);
 
-        let [_ , rom_memory_inspector_node_index] = tree.split_below(cpu_memory_inspector_node_index, 0.38, vec!["ROM Memory Inspector".to_owned(), "ROM Header Inspector".to_owned()]);
-        let [_ , cpu_register_inspector_node_index] = tree.split_below(rom_memory_inspector_node_index, 0.7, vec!["CPU Register Inspector".to_owned()]);
+        let [_ , rom_header_inspector_node_index] = tree.split_below(memory_editor_node_index, 0.38, vec!["ROM Header Inspector".to_owned()]);
+        let [_ , cpu_register_inspector_node_index] = tree.split_below(rom_header_inspector_node_index, 0.7, vec!["CPU Register Inspector".to_owned()]);
+
 
+        tree.split_right(cpu_register_inspector_node_index, 0.5, vec!["CPU Debug Inspector".to_owned(), "Breakpoints".to_owned(), "Call Stack".to_owned(), "Profiler".to_owned(), "Watch".to_owned(), "Controllers".to_owned()]);
 
-        tree.split_right(cpu_register_inspector_node_index, 0.5, vec!["CPU Debug Inspector".to_owned()]);
+        // Logging carries on from the .cdl file of an earlier session
+        if let Err(error) = cpu.bus.load_cdl() {
+            log::warn!("Could not load the code/data log: {}", error);
+        }
+
+        // Breakpoints are kept per game
+        let debugger = load_or_default(storage::rom_data_path(&cpu.bus.cartridge, "breakpoints.json"), Debugger::load, "breakpoints");
+        let watches = load_or_default(storage::rom_data_path(&cpu.bus.cartridge, "watches.json"), WatchList::load, "watches");
+        let keymap = load_or_default(storage::data_path("keymap.json"), KeyMap::load, "the key mapping");
 
         Self {
             context: RunesContext {
                 cpu,
-                page_cpu: 0,
-                page_rom: 0x80,
+                memory_editor: MemoryEditor::default(),
                 chr_rom_texture: None,
+                game_texture: None,
+                debugger,
+                run_to: String::new(),
+                show_cdl: false,
+                profiler_addresses: false,
+                profiler_sort: (2, true),
+                profiler_export: None,
+                breakpoint_form: BreakpointForm::default(),
+                ram_search: RamSearch::default(),
+                ram_search_form: RamSearchForm::default(),
+                watches,
+                watch_form: WatchForm::default(),
+                keymap,
+                binding: None,
             },
             tree
         }
@@ -233,22 +1153,67 @@ impl RunesApp {
 }
 
 impl eframe::App for RunesApp { 
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.context.save_cdl();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // The Game tab aims the Zapper while it is shown, it points off the screen otherwise
+        if let Some(zapper) = self.context.cpu.bus.controllers.zapper_mut() {
+            *zapper = Zapper::default();
+        }
+
         DockArea::new(&mut self.tree)
             .style(Style::from_egui(ctx.style().as_ref()))
             .show(ctx, &mut self.context);
 
-        if ctx.input(|i| i.key_pressed(egui::Key::Space)) {
-            loop {
-                self.context.cpu.clock();
-                if self.context.cpu.complete() {
-                    break;
-                }
-            }
+        let context = &mut self.context;
+
+        if ctx.input(|i| i.key_pressed(egui::Key::F5)) {
+            context.command(Command::Run);
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::F10)) {
+            context.command(Command::StepOver);
+        }
+        if ctx.input(|i| i.modifiers.shift && i.key_pressed(egui::Key::F11)) {
+            context.command(Command::StepOut);
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::F7)) {
+            context.command(Command::StepScanline);
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::F8)) {
+            context.command(Command::StepFrame);
         }
 
-        if ctx.input(|i| i.key_pressed(egui::Key::R)) {
+        // Space, R and the controllers are left to text fields while one is being typed in
+        let typing = ctx.wants_keyboard_input();
+
+        // Buttons are sampled once per frame, nothing is held while typing or binding a key
+        for player in 0..context.keymap.players.len() {
+            let buttons = if typing || context.binding.is_some() { 0 } else { context.keymap.buttons(player, |key| ctx.input(|i| i.key_down(key))) };
+            context.cpu.bus.controllers.set_buttons(player, buttons);
+        }
+
+        if context.debugger.running() {
+            context.debugger.run(&mut context.cpu, CYCLES_PER_FRAME);
+            ctx.request_repaint();
         }
+
+        // Frozen values are written back once per frame, like the cheat engines of other emulators
+        context.ram_search.apply_cheats(&mut context.cpu.bus);
+
+        // Stepping goes through the debugger as well, so breakpoints also stop single steps
+        if !typing && ctx.input(|i| i.key_pressed(egui::Key::Space)) {
+            context.debugger.step(&mut context.cpu);
+        }
+
+        if !typing && ctx.input(|i| i.key_pressed(egui::Key::R)) {
+            context.cpu.reset();
+        }
+
+        let cpu = &context.cpu;
+        context.memory_editor.changes.update(|addr| cpu.peek(addr));
+        context.watches.update(cpu);
     }
 }
 
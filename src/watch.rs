@@ -0,0 +1,235 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+
+use crate::cpu::CPU;
+use crate::expression::Expression;
+use crate::hex_editor::CHANGE_FRAMES;
+use crate::ram_search::{Size, View};
+use crate::storage;
+
+// How the value of a watch is shown
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    U8,
+    S8,
+    U16,
+    S16,
+    Bcd, // Packed, a digit per nibble: $1234 shows as 1234
+    Flags, // Bits, %1000_0001
+}
+
+pub const FORMATS: [Format; 6] = [Format::U8, Format::S8, Format::U16, Format::S16, Format::Bcd, Format::Flags];
+
+impl Format {
+    // Name used in the saved watches
+    fn key(&self) -> &'static str {
+        match self {
+            Format::U8 => "u8",
+            Format::S8 => "s8",
+            Format::U16 => "u16",
+            Format::S16 => "s16",
+            Format::Bcd => "bcd",
+            Format::Flags => "flags",
+        }
+    }
+
+    pub fn show(&self, value: i64) -> String {
+        match self {
+            Format::U8 => format!("{} (${:02X})", value as u8, value as u8),
+            Format::S8 => format!("{} (${:02X})", value as u8 as i8, value as u8),
+            Format::U16 => format!("{} (${:04X})", value as u16, value as u16),
+            Format::S16 => format!("{} (${:04X})", value as u16 as i16, value as u16),
+            Format::Bcd => {
+                let digits = format!("{:X}", value as u32);
+                if digits.chars().all(|c| c.is_ascii_digit()) { digits } else { format!("${} (not BCD)", digits) }
+            },
+            Format::Flags if value as u32 > 0xFF => {
+                let bits = format!("{:016b}", value as u16);
+                format!("%{}_{}", &bits[..8], &bits[8..])
+            },
+            Format::Flags => {
+                let bits = format!("{:08b}", value as u8);
+                format!("%{}_{}", &bits[..4], &bits[4..])
+            },
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FORMATS.iter().copied().find(|format| format.key() == s).ok_or(format!("unknown watch format '{}'", s))
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Format::U8 => write!(f, "u8"),
+            Format::S8 => write!(f, "s8"),
+            Format::U16 => write!(f, "u16"),
+            Format::S16 => write!(f, "s16"),
+            Format::Bcd => write!(f, "BCD"),
+            Format::Flags => write!(f, "flags"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Watch {
+    pub label: String,
+    pub expression: Expression,
+    pub format: Format,
+    pub value: Option<i64>, // As of the last update
+    age: u8, // Frames since the value changed, CHANGE_FRAMES when it hasn't recently
+}
+
+impl Watch {
+    pub fn new(label: &str, expression: Expression, format: Format) -> Self {
+        Watch { label: label.trim().to_string(), expression, format, value: None, age: CHANGE_FRAMES }
+    }
+
+    // The value at addr the way the RAM search reads it, words are little-endian
+    pub fn address(addr: u16, view: View) -> Self {
+        let (source, format) = match (view.size, view.signed) {
+            (Size::Byte, false) => (format!("[${:04X}]", addr), Format::U8),
+            (Size::Byte, true) => (format!("[${:04X}]", addr), Format::S8),
+            (Size::Word, signed) => (
+                format!("[${:04X}] + [${:04X}]*256", addr, addr.wrapping_add(1)),
+                if signed { Format::S16 } else { Format::U16 },
+            ),
+        };
+
+        Watch::new("", Expression::parse(&source).unwrap(), format)
+    }
+
+    pub fn text(&self) -> String {
+        self.value.map_or("-".to_string(), |value| self.format.show(value))
+    }
+
+    // Frames since the value changed, if it did recently
+    pub fn age(&self) -> Option<u8> {
+        Some(self.age).filter(|age| *age < CHANGE_FRAMES)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "label": self.label,
+            "expression": self.expression.source(),
+            "format": self.format.key(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Watch, String> {
+        let expression = Expression::parse(value["expression"].as_str().ok_or("watch without an expression")?)?;
+        let format = value["format"].as_str().map_or(Ok(Format::U8), str::parse)?;
+
+        Ok(Watch::new(value["label"].as_str().unwrap_or(""), expression, format))
+    }
+}
+
+// The Watch tab, evaluated once per frame
+#[derive(Default)]
+pub struct WatchList {
+    pub watches: Vec<Watch>,
+    pub path: Option<PathBuf>, // Where the watches are saved
+}
+
+impl WatchList {
+    // Watches saved at path, none if nothing was saved yet
+    pub fn load(path: PathBuf) -> Result<WatchList, String> {
+        let mut list = WatchList { path: Some(path.clone()), ..WatchList::default() };
+
+        let Some(value) = storage::read_json(&path)? else { return Ok(list) };
+        if let Some(watches) = value["watches"].as_array() {
+            list.watches = watches.iter().map(Watch::from_json).collect::<Result<_, _>>()
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+
+        Ok(list)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+
+        let value = json!({ "watches": self.watches.iter().map(Watch::to_json).collect::<Vec<_>>() });
+        storage::write_json(path, &value)
+    }
+
+    pub fn update(&mut self, cpu: &CPU) {
+        for watch in &mut self.watches {
+            let value = watch.expression.eval(cpu, None);
+
+            if watch.value.is_some_and(|previous| previous != value) {
+                watch.age = 0;
+            } else if watch.age < CHANGE_FRAMES {
+                watch.age += 1;
+            }
+            watch.value = Some(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::testing::cartridge;
+
+    #[test]
+    fn formats() {
+        assert_eq!(Format::U8.show(0xFF), "255 ($FF)");
+        assert_eq!(Format::S8.show(0xFF), "-1 ($FF)");
+        assert_eq!(Format::U16.show(0x1234), "4660 ($1234)");
+        assert_eq!(Format::S16.show(0xFFFE), "-2 ($FFFE)");
+        assert_eq!(Format::Bcd.show(0x0951), "951");
+        assert_eq!(Format::Bcd.show(0x1A), "$1A (not BCD)");
+        assert_eq!(Format::Flags.show(0x81), "%1000_0001");
+        assert_eq!(Format::Flags.show(0x0180), "%00000001_10000000");
+    }
+
+    #[test]
+    fn values_and_changes() {
+        let mut cpu = CPU::new(cartridge("NOP"));
+        cpu.bus.cpu_vram[0x0300] = 0x34;
+        cpu.bus.cpu_vram[0x0301] = 0x12;
+        cpu.bus.cpu_vram[0x0010] = 0x01;
+        cpu.bus.cpu_vram[0x0003] = 0x55;
+        cpu.y_register = 2;
+
+        let mut list = WatchList::default();
+        list.watches.push(Watch::address(0x0300, View { size: Size::Word, signed: false }));
+        list.watches.push(Watch::new("pointer", Expression::parse("[[$10]+Y]").unwrap(), Format::U8));
+        list.update(&cpu);
+
+        assert_eq!(list.watches[0].text(), "4660 ($1234)");
+        assert_eq!(list.watches[1].text(), "85 ($55)");
+        assert_eq!(list.watches[0].age(), None);
+
+        cpu.bus.cpu_vram[0x0300] = 0x35;
+        list.update(&cpu);
+        assert_eq!(list.watches[0].age(), Some(0));
+        assert_eq!(list.watches[1].age(), None);
+    }
+
+    #[test]
+    fn watches_are_saved_and_loaded() {
+        let path = std::env::temp_dir().join(format!("runes-watches-{}.json", std::process::id()));
+
+        let mut list = WatchList { path: Some(path.clone()), ..WatchList::default() };
+        list.watches.push(Watch::new(" lives ", Expression::parse("[$075A]").unwrap(), Format::Bcd));
+        list.watches.push(Watch::address(0x6000, View { size: Size::Word, signed: true }));
+        list.save().unwrap();
+
+        let loaded = WatchList::load(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.watches, list.watches);
+        assert_eq!(loaded.watches[0].label, "lives");
+        assert_eq!(loaded.watches[1].expression.source(), "[$6000] + [$6001]*256");
+        assert!(WatchList::load(path).unwrap().watches.is_empty());
+    }
+}